@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS quotes (
+    id UUID PRIMARY KEY,
+    author TEXT NOT NULL,
+    quote TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    version INT NOT NULL DEFAULT 1
+);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS pagination_tokens (
+    token TEXT PRIMARY KEY,
+    "offset" BIGINT NOT NULL,
+    "limit" BIGINT NOT NULL,
+    sort TEXT NOT NULL,
+    dir TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,10 @@
+-- keyset position and listing filters, so later pages continue where the
+-- previous one stopped with the same bounds
+ALTER TABLE pagination_tokens
+    ADD COLUMN IF NOT EXISTS last_id UUID,
+    ADD COLUMN IF NOT EXISTS last_created_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS last_author TEXT,
+    ADD COLUMN IF NOT EXISTS last_version INT,
+    ADD COLUMN IF NOT EXISTS created_after TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS created_before TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS author TEXT;
@@ -0,0 +1,9 @@
+-- every revision an undo replaced, so old versions stay citable
+CREATE TABLE IF NOT EXISTS quote_history (
+    id UUID NOT NULL,
+    version INT NOT NULL,
+    author TEXT NOT NULL,
+    quote TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (id, version)
+);
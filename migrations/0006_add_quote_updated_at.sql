@@ -0,0 +1,4 @@
+-- set by /19/undo; quotes that were never edited keep it NULL
+ALTER TABLE quotes ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ;
+CREATE INDEX IF NOT EXISTS quotes_version_created_at ON quotes (version, created_at);
+CREATE INDEX IF NOT EXISTS quotes_recently_updated ON quotes (updated_at DESC) WHERE version > 1;
@@ -0,0 +1,3 @@
+-- set by /19/remove; trashed quotes are hidden until restored or purged
+ALTER TABLE quotes ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+CREATE INDEX IF NOT EXISTS quotes_trashed ON quotes (deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,83 @@
+use std::env;
+
+use axum::{
+    Router,
+    body::Body,
+    handler::HandlerWithoutStateExt,
+    http::{HeaderValue, Response, StatusCode, header::CACHE_CONTROL},
+    response::{Html, IntoResponse},
+};
+use tower_http::{services::ServeDir, set_header::SetResponseHeaderLayer};
+
+/// Where static files are served from, overridable through `ASSETS_DIR`.
+const DEFAULT_ASSETS_DIR: &str = "assets";
+
+/// Sent with every file served, so clients revalidate at most hourly.
+const ASSETS_CACHE_CONTROL: HeaderValue = HeaderValue::from_static("public, max-age=3600");
+
+const NOT_FOUND_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head><title>Not found</title></head>
+<body>
+<h1>404 — lost in the snow</h1>
+<p>The elves searched every shelf but couldn't find that file.</p>
+</body>
+</html>
+"#;
+
+pub fn router() -> Router {
+    router_for(&env::var("ASSETS_DIR").unwrap_or_else(|_| DEFAULT_ASSETS_DIR.to_string()))
+}
+
+fn router_for(dir: &str) -> Router {
+    let files = ServeDir::new(dir).not_found_service(assets_not_found.into_service());
+    Router::new()
+        .nest_service("/assets", files)
+        .layer(SetResponseHeaderLayer::if_not_present(
+            CACHE_CONTROL,
+            |response: &Response<Body>| {
+                // the 404 page shouldn't stick around once the file shows up
+                response
+                    .status()
+                    .is_success()
+                    .then_some(ASSETS_CACHE_CONTROL)
+            },
+        ))
+}
+
+async fn assets_not_found() -> impl IntoResponse {
+    (StatusCode::NOT_FOUND, Html(NOT_FOUND_PAGE))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::to_bytes, extract::Request, http::header::CONTENT_TYPE};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn get(uri: &str) -> Response<Body> {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        router_for(DEFAULT_ASSETS_DIR)
+            .oneshot(request)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn missing_asset_gets_the_friendly_404() {
+        let response = get("/assets/no-such-sleigh.png").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(response.headers().get(CACHE_CONTROL).is_none());
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/html; charset=utf-8");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, NOT_FOUND_PAGE);
+    }
+
+    #[tokio::test]
+    async fn assets_are_cached_for_an_hour() {
+        let response = get("/assets/23.html").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CACHE_CONTROL], ASSETS_CACHE_CONTROL);
+    }
+}
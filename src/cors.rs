@@ -0,0 +1,162 @@
+use std::env;
+
+use axum::http::{
+    HeaderName, HeaderValue, Method,
+    header::{AUTHORIZATION, CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH, LOCATION, RETRY_AFTER},
+};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::*;
+
+/// CORS for browser front-ends, allowing the origins listed in the
+/// comma-separated `ALLOWED_ORIGINS`, or any origin for `*`. Without it no
+/// cross-origin request is allowed. Preflight requests are answered by the
+/// layer itself.
+pub fn layer() -> CorsLayer {
+    let origins = match env::var("ALLOWED_ORIGINS") {
+        Ok(origins) if origins.trim() == "*" => AllowOrigin::any(),
+        Ok(origins) => AllowOrigin::list(origins.split(',').filter_map(|origin| {
+            let origin = origin.trim();
+            if origin.is_empty() {
+                return None;
+            }
+            HeaderValue::from_str(origin)
+                .inspect_err(|_| warn!("skipping invalid origin {origin:?} in ALLOWED_ORIGINS"))
+                .ok()
+        })),
+        Err(_) => AllowOrigin::list([]),
+    };
+    layer_for(origins)
+}
+
+fn layer_for(origins: AllowOrigin) -> CorsLayer {
+    CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_headers([
+            AUTHORIZATION,
+            CONTENT_TYPE,
+            IF_MATCH,
+            IF_NONE_MATCH,
+            HeaderName::from_static("idempotency-key"),
+            HeaderName::from_static("if-board-version"),
+        ])
+        .expose_headers([
+            ETAG,
+            LOCATION,
+            RETRY_AFTER,
+            HeaderName::from_static("x-game-end"),
+            HeaderName::from_static("x-placed-at"),
+            HeaderName::from_static("x-board-version"),
+            HeaderName::from_static("x-manifest-format"),
+        ])
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::Body,
+        extract::Request,
+        http::{
+            StatusCode,
+            header::{
+                ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+                ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS,
+                ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN,
+            },
+        },
+        routing::get,
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/", get(|| async { "" }))
+            .layer(layer_for(AllowOrigin::list([HeaderValue::from_static(
+                "https://elf.example",
+            )])))
+    }
+
+    #[tokio::test]
+    async fn custom_response_headers_are_exposed() {
+        let request = Request::get("/")
+            .header(ORIGIN, "https://elf.example")
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        let exposed = response.headers()[ACCESS_CONTROL_EXPOSE_HEADERS]
+            .to_str()
+            .unwrap()
+            .to_string();
+        for header in [
+            "x-game-end",
+            "x-placed-at",
+            "x-board-version",
+            "x-manifest-format",
+        ] {
+            assert!(
+                exposed.split(',').any(|name| name.trim() == header),
+                "{header}"
+            );
+        }
+    }
+
+    fn preflight(origin: &str) -> Request {
+        Request::options("/")
+            .header(ORIGIN, origin)
+            .header(ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+            .header(ACCESS_CONTROL_REQUEST_HEADERS, "if-match,content-type")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn preflight_allows_a_configured_origin() {
+        let response = app()
+            .oneshot(preflight("https://elf.example"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_ORIGIN], "https://elf.example");
+        let methods = headers[ACCESS_CONTROL_ALLOW_METHODS].to_str().unwrap();
+        assert!(
+            methods.split(',').any(|method| method.trim() == "PUT"),
+            "{methods}"
+        );
+        let allowed = headers[ACCESS_CONTROL_ALLOW_HEADERS].to_str().unwrap();
+        for header in ["if-match", "content-type"] {
+            assert!(
+                allowed.split(',').any(|name| name.trim() == header),
+                "{allowed}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn other_origins_are_not_allowed() {
+        let response = app()
+            .oneshot(preflight("https://grinch.example"))
+            .await
+            .unwrap();
+        assert!(
+            response
+                .headers()
+                .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+                .is_none()
+        );
+        let request = Request::get("/")
+            .header(ORIGIN, "https://grinch.example")
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        assert!(
+            response
+                .headers()
+                .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,1294 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::{Path, Query, Request, State},
+    http::{
+        HeaderMap, HeaderName, HeaderValue, StatusCode,
+        header::{ACCEPT, CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, UPGRADE},
+    },
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use futures_util::{SinkExt, StreamExt};
+use hyper_util::rt::TokioIo;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, broadcast};
+use tokio_tungstenite::{
+    WebSocketStream,
+    tungstenite::{Message, handshake::derive_accept_key, protocol::Role},
+};
+use tracing::*;
+
+use crate::metrics;
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/12/random-board", get(day_12_random_board))
+        .route("/12/place/{team}/{column}", post(day_12_place))
+        .route("/12/board", get(day_12_board))
+        .route("/12/status", get(day_12_status))
+        .route("/12/reset", post(day_12_reset))
+        .route("/12/new/{rows}/{cols}", post(day_12_new))
+        .route("/12/undo", post(day_12_undo))
+        .route("/12/seed/{value}", post(day_12_seed))
+        .route("/12/history", get(day_12_history))
+        .route("/12/watch", get(day_12_watch))
+        .route("/12/suggest/{team}", get(day_12_suggest))
+        .route("/12/simulate/{team}/{column}", post(day_12_simulate))
+        .route("/12/stats", get(day_12_stats))
+        .route("/12/reset-stats", post(day_12_reset_stats))
+        .with_state(Arc::new(Day12AppState {
+            game: Mutex::new(Game::new()),
+            updates: broadcast::channel(BOARD_UPDATES_CAPACITY).0,
+            stats: Mutex::new(GameStats::default()),
+            version: AtomicU64::new(0),
+        }))
+}
+
+struct Day12AppState {
+    game: Mutex<Game>,
+    /// Rendered board after every change, for `/12/watch` subscribers.
+    updates: broadcast::Sender<String>,
+    /// Outcomes of every game played, kept across `/12/reset`.
+    stats: Mutex<GameStats>,
+    /// Bumped on every board change, including a reset or resize that
+    /// replaces the `Game`. Only updated while `game` is locked.
+    version: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+struct GameStats {
+    cookie_wins: u64,
+    milk_wins: u64,
+    draws: u64,
+}
+
+impl Day12AppState {
+    /// Counts the outcome of a game that just finished, or takes it back when
+    /// an undo reopens the game, so each finished game counts once.
+    async fn tally(&self, winner: Option<GameItem>, finished: bool) {
+        let mut stats = self.stats.lock().await;
+        let counter = match winner {
+            Some(GameItem::Cookie) => &mut stats.cookie_wins,
+            Some(GameItem::Milk) => &mut stats.milk_wins,
+            _ => &mut stats.draws,
+        };
+        if finished {
+            *counter += 1;
+        } else {
+            *counter = counter.saturating_sub(1);
+        }
+    }
+
+    /// Renders the board and sends it to any watchers.
+    fn publish(&self, game: &Game) -> String {
+        self.version.fetch_add(1, Ordering::SeqCst);
+        let board = game.print_board();
+        // no receivers just means nobody is watching
+        let _ = self.updates.send(board.clone());
+        board
+    }
+}
+
+/// Board updates buffered per watcher; slower watchers skip to newer boards.
+const BOARD_UPDATES_CAPACITY: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameItem {
+    Wall,
+    Empty,
+    Cookie,
+    Milk,
+}
+
+/// How the text board draws its cells.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Charset {
+    #[default]
+    Emoji,
+    /// For terminals without emoji fonts.
+    Ascii,
+}
+
+impl Charset {
+    fn glyph(self, item: GameItem) -> char {
+        match (self, item) {
+            (Charset::Emoji, GameItem::Wall) => '⬜',
+            (Charset::Emoji, GameItem::Empty) => '⬛',
+            (Charset::Emoji, GameItem::Cookie) => '🍪',
+            (Charset::Emoji, GameItem::Milk) => '🥛',
+            (Charset::Ascii, GameItem::Wall) => '#',
+            (Charset::Ascii, GameItem::Empty) => '.',
+            (Charset::Ascii, GameItem::Cookie) => 'C',
+            (Charset::Ascii, GameItem::Milk) => 'M',
+        }
+    }
+}
+
+/// Playfield size used by `/12/reset`.
+const DEFAULT_BOARD_SIZE: usize = 4;
+/// Largest playfield side accepted by `/12/new`.
+const MAX_BOARD_SIZE: usize = 16;
+/// Seed used for `/12/random-board` unless `/12/seed` or `/12/reset?seed=` says otherwise.
+const DEFAULT_SEED: u64 = 2024;
+/// Number of aligned items needed to win.
+const WIN_LENGTH: usize = 4;
+
+/// How a history entry got onto the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum MoveSource {
+    Place,
+    Random,
+}
+
+/// One placement in the game history. Coordinates index into the board, walls
+/// included, so `column` is the same as in `/12/place`.
+#[derive(Debug, Clone, Copy, Serialize)]
+struct Move {
+    turn: usize,
+    team: GameItem,
+    row: usize,
+    column: usize,
+    source: MoveSource,
+}
+
+/// Row and column steps for horizontal, vertical, diagonal and anti-diagonal
+/// lines, in the order they are checked.
+const DIRECTIONS: [(usize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+/// Playable rows plus a bottom wall, each row walled on both sides.
+pub type Board = Vec<Vec<GameItem>>;
+
+/// The team with a line of `WIN_LENGTH` on `board`, if any. Works on any
+/// board size, since the dimensions come from the board itself.
+pub fn detect_winner(board: &Board) -> Option<GameItem> {
+    winning_line(board).map(|cells| board[cells[0].0][cells[0].1])
+}
+
+/// The first winning line on `board`, scanning `DIRECTIONS` in order.
+fn winning_line(board: &Board) -> Option<Vec<(usize, usize)>> {
+    // the last row and the outer columns are walls
+    let height = board.len().saturating_sub(1);
+    let width = board.first().map_or(0, |row| row.len().saturating_sub(2));
+    DIRECTIONS.into_iter().find_map(|(row_step, column_step)| {
+        (0..height).find_map(|row| {
+            (1..=width).find_map(|column| line_from(board, row, column, row_step, column_step))
+        })
+    })
+}
+
+/// Returns the `WIN_LENGTH` cells of a single team's line starting at
+/// `(row, column)`, if there is one.
+fn line_from(
+    board: &Board,
+    row: usize,
+    column: usize,
+    row_step: usize,
+    column_step: isize,
+) -> Option<Vec<(usize, usize)>> {
+    let first = board[row][column];
+    if !matches!(first, GameItem::Cookie | GameItem::Milk) {
+        return None;
+    }
+    (0..WIN_LENGTH)
+        .map(|step| {
+            // walls never match a team, so running into them ends the line
+            let cell = (
+                row + row_step * step,
+                column.checked_add_signed(column_step * step as isize)?,
+            );
+            (board.get(cell.0)?.get(cell.1) == Some(&first)).then_some(cell)
+        })
+        .collect()
+}
+
+/// The board is `height` playable rows plus a bottom wall, and `width`
+/// playable columns plus a wall on each side.
+#[derive(Clone)]
+struct Game {
+    width: usize,
+    height: usize,
+    board: Board,
+    winner: Option<GameItem>,
+    board_full: bool,
+    /// `(row, column)` of the line that made `winner` win.
+    winning_cells: Vec<(usize, usize)>,
+    rng: StdRng,
+    /// Every placement since the game was created, oldest first.
+    history: Vec<Move>,
+}
+
+impl Game {
+    fn new() -> Self {
+        Self::with_size(DEFAULT_BOARD_SIZE, DEFAULT_BOARD_SIZE)
+    }
+
+    fn with_size(height: usize, width: usize) -> Self {
+        Self {
+            width,
+            height,
+            board: Self::empty_board(height, width),
+            winner: None,
+            board_full: false,
+            winning_cells: Vec::new(),
+            rng: StdRng::seed_from_u64(DEFAULT_SEED),
+            history: Vec::new(),
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new();
+        self.publish_metrics();
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn is_column_full(&self, column: usize) -> bool {
+        self.board[0][column] != GameItem::Empty
+    }
+
+    fn is_finished(&self) -> bool {
+        self.winner.is_some() || self.board_full
+    }
+
+    /// Drops `item` into `column`, returning the row it landed in.
+    fn drop_item(&mut self, item: GameItem, column: usize) -> Option<usize> {
+        let row = (0..self.height)
+            .rev()
+            .find(|&row| self.board[row][column] == GameItem::Empty)?;
+        self.board[row][column] = item;
+        Some(row)
+    }
+
+    /// Drops `item` into `column`, returning the `(row, column)` it filled,
+    /// or `None` if the column was full.
+    fn put_item(&mut self, item: GameItem, column: usize) -> Option<(usize, usize)> {
+        let row = self.play(item, column);
+        self.publish_metrics();
+        row.map(|row| (row, column))
+    }
+
+    /// Places `item` like `put_item`, but leaves the metrics alone so a
+    /// cloned game can be played out as a preview.
+    fn play(&mut self, item: GameItem, column: usize) -> Option<usize> {
+        let row = self.drop_item(item, column);
+        if let Some(row) = row {
+            self.record(item, row, column, MoveSource::Place);
+        }
+        // check wins
+        self.check_win();
+
+        // check full
+        self.board_full = self.board[0].iter().all(|&item| item != GameItem::Empty);
+        row
+    }
+
+    fn record(&mut self, team: GameItem, row: usize, column: usize, source: MoveSource) {
+        self.history.push(Move {
+            turn: self.history.len() + 1,
+            team,
+            row,
+            column,
+            source,
+        });
+    }
+
+    /// Reverts the latest `put_item` placement, returning false if there is none.
+    /// A random fill overwrites the board, so nothing before it can be undone.
+    fn undo(&mut self) -> bool {
+        let Some(&Move {
+            row,
+            column,
+            source: MoveSource::Place,
+            ..
+        }) = self.history.last()
+        else {
+            return false;
+        };
+        self.history.pop();
+        self.board[row][column] = GameItem::Empty;
+        self.winner = None;
+        self.winning_cells.clear();
+        self.check_win();
+        self.board_full = false;
+        self.publish_metrics();
+        true
+    }
+
+    /// Whether dropping `team` into `column` would win the game outright.
+    fn wins_with(&self, team: GameItem, column: usize) -> bool {
+        let mut simulated = self.clone();
+        if simulated.drop_item(team, column).is_none() {
+            return false;
+        }
+        detect_winner(&simulated.board) == Some(team)
+    }
+
+    /// Picks a column for `team`: an immediate win, else a block of the
+    /// opponent's immediate win, else the most central open column.
+    fn suggest(&self, team: GameItem) -> Option<usize> {
+        let opponent = match team {
+            GameItem::Cookie => GameItem::Milk,
+            _ => GameItem::Cookie,
+        };
+        let mut open = (1..=self.width)
+            .filter(|&column| !self.is_column_full(column))
+            .collect::<Vec<_>>();
+        // ties between the two middle columns of an even board go left
+        open.sort_by_key(|&column| (2 * column).abs_diff(self.width + 1));
+        open.iter()
+            .find(|&&column| self.wins_with(team, column))
+            .or_else(|| {
+                open.iter()
+                    .find(|&&column| self.wins_with(opponent, column))
+            })
+            .or(open.first())
+            .copied()
+    }
+
+    fn put_random_item(&mut self, item: GameItem, row: usize, column: usize) {
+        self.board[row][column] = item;
+        self.record(item, row, column, MoveSource::Random);
+        // check wins
+        self.check_win();
+        self.publish_metrics();
+    }
+
+    fn publish_metrics(&self) {
+        metrics::GAME_FINISHED.store(self.is_finished(), Ordering::SeqCst);
+    }
+
+    fn check_win(&mut self) {
+        if let Some(cells) = winning_line(&self.board) {
+            let (row, column) = cells[0];
+            self.winner = Some(self.board[row][column]);
+            self.winning_cells = cells;
+        }
+    }
+
+    fn print_board(&self) -> String {
+        self.render(Charset::Emoji)
+    }
+
+    fn render(&self, charset: Charset) -> String {
+        let mut board = String::new();
+        for row in &self.board {
+            for &item in row {
+                board.push(charset.glyph(item));
+            }
+            board.push('\n');
+        }
+        if let Some(winner) = self.winner {
+            board.push(charset.glyph(winner));
+            board.push_str(" wins!\n");
+        } else if self.board_full {
+            board.push_str("No winner.\n");
+        }
+        board
+    }
+
+    fn to_json(&self) -> BoardJson<'_> {
+        BoardJson {
+            board: &self.board,
+            winner: self.winner,
+            board_full: self.board_full,
+            winning_cells: &self.winning_cells,
+            columns_full: (1..=self.width)
+                .map(|column| self.is_column_full(column))
+                .collect(),
+        }
+    }
+
+    fn status(&self) -> GameStatus {
+        GameStatus {
+            finished: self.is_finished(),
+            winner: self.winner,
+            board_full: self.board_full,
+        }
+    }
+
+    fn empty_board(height: usize, width: usize) -> Board {
+        let mut board = vec![vec![GameItem::Empty; width + 2]; height + 1];
+        for row in board.iter_mut().take(height) {
+            row[0] = GameItem::Wall;
+            row[width + 1] = GameItem::Wall;
+        }
+        board[height].fill(GameItem::Wall);
+        board
+    }
+}
+
+/// Whether `Accept` lists `application/json`.
+fn day_12_accepts_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| {
+            accept.split(',').any(|media_type| {
+                media_type.split(';').next().map(str::trim) == Some("application/json")
+            })
+        })
+}
+
+/// Renders the board as JSON or as the emoji grid.
+fn day_12_board_response(
+    status: StatusCode,
+    game: &Game,
+    json: bool,
+    charset: Charset,
+) -> Response {
+    if json {
+        (status, Json(game.to_json())).into_response()
+    } else {
+        (status, game.render(charset)).into_response()
+    }
+}
+
+#[derive(Deserialize)]
+struct Day12CharsetQuery {
+    #[serde(default)]
+    charset: Charset,
+}
+
+async fn day_12_random_board(
+    State(state): State<Arc<Day12AppState>>,
+    Query(query): Query<Day12CharsetQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let mut game = state.game.lock().await;
+    let was_finished = game.is_finished();
+    for i in 0..game.height {
+        for j in 1..=game.width {
+            let team = if game.rng.r#gen::<bool>() {
+                GameItem::Cookie
+            } else {
+                GameItem::Milk
+            };
+            game.put_random_item(team, i, j);
+        }
+    }
+    if !was_finished && game.is_finished() {
+        state.tally(game.winner, true).await;
+    }
+    state.publish(&game);
+    day_12_board_response(
+        StatusCode::OK,
+        &game,
+        day_12_accepts_json(&headers),
+        query.charset,
+    )
+}
+
+/// Set on a refused `/12/place` to say how the game ended: `winner` or `full`.
+const X_GAME_END: HeaderName = HeaderName::from_static("x-game-end");
+
+/// Set on a successful `/12/place` to the `row,column` the piece landed in, in
+/// the same coordinates as the JSON board.
+const X_PLACED_AT: HeaderName = HeaderName::from_static("x-placed-at");
+
+async fn day_12_place(
+    State(state): State<Arc<Day12AppState>>,
+    Path((team, column)): Path<(String, i32)>,
+    Query(query): Query<Day12CharsetQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let team = match team.as_str() {
+        "cookie" => GameItem::Cookie,
+        "milk" => GameItem::Milk,
+        _ => {
+            return StatusCode::BAD_REQUEST.into_response();
+        }
+    };
+    let json = day_12_accepts_json(&headers);
+    let mut game = state.game.lock().await;
+    if column < 1 || column as usize > game.width {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+    let column = column as usize;
+    if game.is_finished() {
+        // a full board can also have a winner; the win is what ended the game
+        let end = if game.winner.is_some() {
+            "winner"
+        } else {
+            "full"
+        };
+        let mut response =
+            day_12_board_response(StatusCode::SERVICE_UNAVAILABLE, &game, json, query.charset);
+        response
+            .headers_mut()
+            .insert(X_GAME_END, HeaderValue::from_static(end));
+        return response;
+    }
+    if game.is_column_full(column) {
+        return day_12_board_response(StatusCode::SERVICE_UNAVAILABLE, &game, json, query.charset);
+    }
+    let placed_at = game.put_item(team, column);
+    if game.is_finished() {
+        state.tally(game.winner, true).await;
+    }
+    state.publish(&game);
+    let mut response = day_12_board_response(StatusCode::OK, &game, json, query.charset);
+    if let Some((row, column)) = placed_at {
+        response.headers_mut().insert(
+            X_PLACED_AT,
+            HeaderValue::from_str(&format!("{row},{column}")).expect("digits are a valid header"),
+        );
+    }
+    response
+}
+
+/// Shows the board `/12/place` would produce, without changing the live game.
+async fn day_12_simulate(
+    State(state): State<Arc<Day12AppState>>,
+    Path((team, column)): Path<(String, i32)>,
+    Query(query): Query<Day12CharsetQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let team = match team.as_str() {
+        "cookie" => GameItem::Cookie,
+        "milk" => GameItem::Milk,
+        _ => {
+            return StatusCode::BAD_REQUEST.into_response();
+        }
+    };
+    let json = day_12_accepts_json(&headers);
+    let mut preview = {
+        let game = state.game.lock().await;
+        if column < 1 || column as usize > game.width {
+            return StatusCode::BAD_REQUEST.into_response();
+        }
+        if game.is_finished() || game.is_column_full(column as usize) {
+            return day_12_board_response(
+                StatusCode::SERVICE_UNAVAILABLE,
+                &game,
+                json,
+                query.charset,
+            );
+        }
+        game.clone()
+    };
+    preview.play(team, column as usize);
+    day_12_board_response(StatusCode::OK, &preview, json, query.charset)
+}
+
+/// Structured form of the board. Coordinates index into `board`, walls included.
+#[derive(Serialize)]
+struct BoardJson<'a> {
+    board: &'a [Vec<GameItem>],
+    winner: Option<GameItem>,
+    board_full: bool,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    winning_cells: &'a [(usize, usize)],
+    /// Per playable column, left to right, whether `/12/place` would refuse it.
+    columns_full: Vec<bool>,
+}
+
+/// Whether the game is over, without the board itself. `winner` stays `None`
+/// when the board filled up without a line.
+#[derive(Serialize)]
+struct GameStatus {
+    finished: bool,
+    winner: Option<GameItem>,
+    board_full: bool,
+}
+
+#[derive(Deserialize)]
+struct Day12BoardQuery {
+    format: Option<String>,
+    #[serde(default)]
+    charset: Charset,
+}
+
+/// Sent with `/12/board`; a poll echoing it in `If-Board-Version` gets a 304
+/// until the board changes.
+const X_BOARD_VERSION: HeaderName = HeaderName::from_static("x-board-version");
+const IF_BOARD_VERSION: HeaderName = HeaderName::from_static("if-board-version");
+
+async fn day_12_board(
+    State(state): State<Arc<Day12AppState>>,
+    Query(query): Query<Day12BoardQuery>,
+    headers: HeaderMap,
+) -> Response {
+    // an explicit `?format=` wins over `Accept`
+    let json = match query.format.as_deref() {
+        None => day_12_accepts_json(&headers),
+        Some("text") => false,
+        Some("json") => true,
+        Some(_) => return StatusCode::BAD_REQUEST.into_response(),
+    };
+    let game = state.game.lock().await;
+    let version = state.version.load(Ordering::SeqCst);
+    let unchanged = headers
+        .get(IF_BOARD_VERSION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        == Some(version);
+    let mut response = if unchanged {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        day_12_board_response(StatusCode::OK, &game, json, query.charset)
+    };
+    response
+        .headers_mut()
+        .insert(X_BOARD_VERSION, HeaderValue::from(version));
+    response
+}
+
+#[derive(Deserialize)]
+struct Day12ResetQuery {
+    seed: Option<u64>,
+}
+
+async fn day_12_reset(
+    State(state): State<Arc<Day12AppState>>,
+    Query(query): Query<Day12ResetQuery>,
+) -> impl IntoResponse {
+    let mut game = state.game.lock().await;
+    game.reset();
+    if let Some(seed) = query.seed {
+        game.reseed(seed);
+    }
+    state.publish(&game)
+}
+
+async fn day_12_seed(
+    State(state): State<Arc<Day12AppState>>,
+    Path(seed): Path<u64>,
+) -> impl IntoResponse {
+    let mut game = state.game.lock().await;
+    game.reseed(seed);
+    game.print_board()
+}
+
+async fn day_12_new(
+    State(state): State<Arc<Day12AppState>>,
+    Path((rows, cols)): Path<(usize, usize)>,
+) -> (StatusCode, Body) {
+    if !(1..=MAX_BOARD_SIZE).contains(&rows) || !(1..=MAX_BOARD_SIZE).contains(&cols) {
+        return (StatusCode::BAD_REQUEST, Body::empty());
+    }
+    let mut game = state.game.lock().await;
+    *game = Game::with_size(rows, cols);
+    game.publish_metrics();
+    (StatusCode::OK, Body::from(state.publish(&game)))
+}
+
+async fn day_12_undo(State(state): State<Arc<Day12AppState>>) -> (StatusCode, Body) {
+    let mut game = state.game.lock().await;
+    // capture the outcome before undoing, since undo clears the winner
+    let (was_finished, winner) = (game.is_finished(), game.winner);
+    if !game.undo() {
+        return (StatusCode::BAD_REQUEST, Body::from(game.print_board()));
+    }
+    if was_finished && !game.is_finished() {
+        state.tally(winner, false).await;
+    }
+    (StatusCode::OK, Body::from(state.publish(&game)))
+}
+
+async fn day_12_status(State(state): State<Arc<Day12AppState>>) -> impl IntoResponse {
+    Json(state.game.lock().await.status())
+}
+
+async fn day_12_stats(State(state): State<Arc<Day12AppState>>) -> impl IntoResponse {
+    Json(*state.stats.lock().await)
+}
+
+async fn day_12_reset_stats(State(state): State<Arc<Day12AppState>>) -> impl IntoResponse {
+    let mut stats = state.stats.lock().await;
+    *stats = GameStats::default();
+    Json(*stats)
+}
+
+async fn day_12_history(State(state): State<Arc<Day12AppState>>) -> impl IntoResponse {
+    let game = state.game.lock().await;
+    Json(game.history.clone())
+}
+
+async fn day_12_suggest(
+    State(state): State<Arc<Day12AppState>>,
+    Path(team): Path<String>,
+) -> (StatusCode, Body) {
+    let team = match team.as_str() {
+        "cookie" => GameItem::Cookie,
+        "milk" => GameItem::Milk,
+        _ => {
+            return (StatusCode::BAD_REQUEST, Body::empty());
+        }
+    };
+    let game = state.game.lock().await;
+    if game.is_finished() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Body::from(game.print_board()),
+        );
+    }
+    match game.suggest(team) {
+        Some(column) => (StatusCode::OK, Body::from(column.to_string())),
+        None => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Body::from(game.print_board()),
+        ),
+    }
+}
+
+/// Upgrades to a WebSocket that receives the rendered board on every change.
+async fn day_12_watch(State(state): State<Arc<Day12AppState>>, mut request: Request) -> Response {
+    let is_upgrade = request
+        .headers()
+        .get(UPGRADE)
+        .is_some_and(|upgrade| upgrade.as_bytes().eq_ignore_ascii_case(b"websocket"));
+    let Some(key) = request
+        .headers()
+        .get(SEC_WEBSOCKET_KEY)
+        .filter(|_| is_upgrade)
+    else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let Ok(accept) = HeaderValue::from_str(&derive_accept_key(key.as_bytes())) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let on_upgrade = hyper::upgrade::on(&mut request);
+    tokio::spawn(async move {
+        match on_upgrade.await {
+            Ok(upgraded) => {
+                let socket =
+                    WebSocketStream::from_raw_socket(TokioIo::new(upgraded), Role::Server, None)
+                        .await;
+                day_12_watch_socket(state, socket).await;
+            }
+            Err(err) => warn!("watch: error upgrading connection: {:?}", err),
+        }
+    });
+    (
+        StatusCode::SWITCHING_PROTOCOLS,
+        [
+            (UPGRADE, HeaderValue::from_static("websocket")),
+            (CONNECTION, HeaderValue::from_static("upgrade")),
+            (SEC_WEBSOCKET_ACCEPT, accept),
+        ],
+    )
+        .into_response()
+}
+
+async fn day_12_watch_socket<S>(state: Arc<Day12AppState>, mut socket: WebSocketStream<S>)
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    // subscribe under the lock so no update lands between the snapshot and the feed
+    let (mut updates, board) = {
+        let game = state.game.lock().await;
+        (state.updates.subscribe(), game.print_board())
+    };
+    if socket.send(Message::text(board)).await.is_err() {
+        return;
+    }
+    loop {
+        tokio::select! {
+            update = updates.recv() => match update {
+                Ok(board) => {
+                    if socket.send(Message::text(board)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            // watchers only listen; anything but a close or error is ignored
+            message = socket.next() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::to_bytes, http::header::CONTENT_TYPE};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn send(app: &Router, request: Request) -> (StatusCode, HeaderMap, String) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let body = to_bytes(body, usize::MAX).await.unwrap();
+        (
+            parts.status,
+            parts.headers,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    fn post(uri: &str) -> Request {
+        Request::post(uri).body(Body::empty()).unwrap()
+    }
+
+    fn get(uri: &str) -> Request {
+        Request::get(uri).body(Body::empty()).unwrap()
+    }
+
+    /// Places each `(team, column)` in turn, returning the last response.
+    async fn play(app: &Router, moves: &[(&str, usize)]) -> (StatusCode, HeaderMap, String) {
+        let mut last = None;
+        for (team, column) in moves {
+            last = Some(
+                send(
+                    app,
+                    post(&format!("/12/place/{team}/{column}?charset=ascii")),
+                )
+                .await,
+            );
+        }
+        last.expect("at least one move")
+    }
+
+    /// Parses a board drawn with the ASCII charset, walls included.
+    fn board(rows: &[&str]) -> Board {
+        rows.iter()
+            .map(|row| {
+                row.chars()
+                    .map(|glyph| match glyph {
+                        '#' => GameItem::Wall,
+                        '.' => GameItem::Empty,
+                        'C' => GameItem::Cookie,
+                        'M' => GameItem::Milk,
+                        _ => panic!("unknown glyph {glyph:?}"),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn detects_every_orientation_on_the_default_board() {
+        let cases = [
+            (
+                ["#....#", "#....#", "#CCCC#", "#MMM.#"],
+                GameItem::Cookie,
+                vec![(2, 1), (2, 2), (2, 3), (2, 4)],
+            ),
+            (
+                ["#.M..#", "#.M..#", "#CMC.#", "#CMC.#"],
+                GameItem::Milk,
+                vec![(0, 2), (1, 2), (2, 2), (3, 2)],
+            ),
+            (
+                ["#C...#", "#MC..#", "#MMC.#", "#CMMC#"],
+                GameItem::Cookie,
+                vec![(0, 1), (1, 2), (2, 3), (3, 4)],
+            ),
+            (
+                ["#...M#", "#..MC#", "#.MCC#", "#MCCM#"],
+                GameItem::Milk,
+                vec![(0, 4), (1, 3), (2, 2), (3, 1)],
+            ),
+        ];
+        for (rows, winner, cells) in cases {
+            let mut rows = rows.to_vec();
+            rows.push("######");
+            let board = board(&rows);
+            assert_eq!(detect_winner(&board), Some(winner), "{rows:?}");
+            assert_eq!(winning_line(&board), Some(cells), "{rows:?}");
+        }
+    }
+
+    #[test]
+    fn detects_lines_away_from_the_edges_of_a_resized_board() {
+        let anti_diagonal = board(&[
+            "#.......#",
+            "#.....C.#",
+            "#....CM.#",
+            "#...CMM.#",
+            "#..CMMC.#",
+            "#.MCCMC.#",
+            "#########",
+        ]);
+        assert_eq!(detect_winner(&anti_diagonal), Some(GameItem::Cookie));
+        assert_eq!(
+            winning_line(&anti_diagonal),
+            Some(vec![(1, 6), (2, 5), (3, 4), (4, 3)])
+        );
+        let diagonal = board(&[
+            "#.......#",
+            "#.......#",
+            "#..M....#",
+            "#..CM...#",
+            "#..CCM..#",
+            "#..CCCM.#",
+            "#########",
+        ]);
+        assert_eq!(
+            winning_line(&diagonal),
+            Some(vec![(2, 3), (3, 4), (4, 5), (5, 6)])
+        );
+    }
+
+    #[test]
+    fn three_in_a_row_or_a_broken_line_is_no_win() {
+        let board = board(&["#.....#", "#M....#", "#M..C.#", "#MCCMC#", "#######"]);
+        assert_eq!(detect_winner(&board), None);
+        assert_eq!(detect_winner(&Game::with_size(3, 3).board), None);
+    }
+
+    #[tokio::test]
+    async fn new_board_is_resized_and_reset_restores_the_default() {
+        let app = router();
+        let (status, _, body) = send(&app, post("/12/new/5/5")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "⬜⬛⬛⬛⬛⬛⬜\n".repeat(5) + "⬜⬜⬜⬜⬜⬜⬜\n");
+        let (status, _, _) = send(&app, post("/12/new/0/5")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (_, _, body) = send(&app, post("/12/reset")).await;
+        assert_eq!(body, "⬜⬛⬛⬛⬛⬜\n".repeat(4) + "⬜⬜⬜⬜⬜⬜\n");
+    }
+
+    #[tokio::test]
+    async fn five_by_five_board_is_won_in_the_last_column() {
+        let app = router();
+        send(&app, post("/12/new/5/5")).await;
+        let (status, _, body) = play(
+            &app,
+            &[
+                ("cookie", 5),
+                ("milk", 1),
+                ("cookie", 5),
+                ("milk", 1),
+                ("cookie", 5),
+                ("milk", 2),
+                ("cookie", 5),
+            ],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.ends_with("C wins!\n"), "{body}");
+        let (status, _, _) = send(&app, post("/12/place/milk/6")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn undo_without_moves_is_rejected() {
+        let app = router();
+        let (status, _, body) = send(&app, post("/12/undo")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body, Game::new().print_board());
+    }
+
+    #[tokio::test]
+    async fn undoing_the_winning_move_reopens_the_game() {
+        let app = router();
+        let (_, _, won) = play(
+            &app,
+            &[
+                ("cookie", 1),
+                ("milk", 2),
+                ("cookie", 1),
+                ("milk", 2),
+                ("cookie", 1),
+                ("milk", 2),
+                ("cookie", 1),
+            ],
+        )
+        .await;
+        assert!(won.ends_with("C wins!\n"), "{won}");
+        let (status, _, body) = send(&app, post("/12/undo")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!body.contains("wins!"), "{body}");
+        let (status, _, body) = play(&app, &[("milk", 2)]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.ends_with("M wins!\n"), "{body}");
+    }
+
+    #[tokio::test]
+    async fn random_fill_cannot_be_undone() {
+        let app = router();
+        send(&app, get("/12/random-board")).await;
+        let (status, _, _) = send(&app, post("/12/undo")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn seeds_pick_the_random_board() {
+        let app = router();
+        let mut boards = Vec::new();
+        for seed in [1, 2, 1] {
+            send(&app, post(&format!("/12/reset?seed={seed}"))).await;
+            boards.push(send(&app, get("/12/random-board")).await.2);
+        }
+        assert_ne!(boards[0], boards[1]);
+        assert_eq!(boards[0], boards[2]);
+
+        send(&app, post("/12/reset")).await;
+        send(&app, post("/12/seed/2")).await;
+        assert_eq!(send(&app, get("/12/random-board")).await.2, boards[1]);
+    }
+
+    async fn history(app: &Router) -> serde_json::Value {
+        serde_json::from_str(&send(app, get("/12/history")).await.2).unwrap()
+    }
+
+    #[tokio::test]
+    async fn history_logs_placements_until_reset() {
+        let app = router();
+        play(&app, &[("cookie", 2), ("milk", 2)]).await;
+        assert_eq!(
+            history(&app).await,
+            serde_json::json!([
+                {"turn": 1, "team": "cookie", "row": 3, "column": 2, "source": "place"},
+                {"turn": 2, "team": "milk", "row": 2, "column": 2, "source": "place"},
+            ])
+        );
+        send(&app, post("/12/reset")).await;
+        assert_eq!(history(&app).await, serde_json::json!([]));
+
+        play(&app, &[("cookie", 1)]).await;
+        send(&app, post("/12/new/5/6")).await;
+        assert_eq!(history(&app).await, serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn random_placements_are_marked() {
+        let app = router();
+        send(&app, get("/12/random-board")).await;
+        let history = history(&app).await;
+        let moves = history.as_array().unwrap();
+        assert_eq!(moves.len(), 16);
+        assert!(moves.iter().all(|entry| entry["source"] == "random"));
+    }
+
+    fn accepting(request: Request, accept: &str) -> Request {
+        let (mut parts, body) = request.into_parts();
+        parts
+            .headers
+            .insert(ACCEPT, HeaderValue::from_str(accept).unwrap());
+        Request::from_parts(parts, body)
+    }
+
+    #[tokio::test]
+    async fn board_endpoints_answer_json_when_asked() {
+        let app = router();
+        let (_, headers, body) = send(
+            &app,
+            accepting(post("/12/place/cookie/1"), "application/json"),
+        )
+        .await;
+        assert_eq!(headers[CONTENT_TYPE], "application/json");
+        let board: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            board["board"][3],
+            serde_json::json!(["wall", "cookie", "empty", "empty", "empty", "wall"])
+        );
+        assert_eq!(board["winner"], serde_json::Value::Null);
+        assert_eq!(board["board_full"], false);
+
+        let (_, _, body) = send(&app, accepting(get("/12/board"), "text/plain")).await;
+        assert_eq!(body.lines().nth(3), Some("⬜🍪⬛⬛⬛⬜"));
+
+        let (_, _, body) = send(&app, accepting(get("/12/random-board"), "application/json")).await;
+        let board: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert!(board["winner"].is_string() || board["board_full"] == true);
+    }
+
+    /// Cookie stacks column 1 while milk stacks column 2; cookie wins on the last move.
+    const COOKIE_WINS: [(&str, usize); 7] = [
+        ("cookie", 1),
+        ("milk", 2),
+        ("cookie", 1),
+        ("milk", 2),
+        ("cookie", 1),
+        ("milk", 2),
+        ("cookie", 1),
+    ];
+
+    async fn stats(app: &Router) -> serde_json::Value {
+        serde_json::from_str(&send(app, get("/12/stats")).await.2).unwrap()
+    }
+
+    #[tokio::test]
+    async fn a_win_is_counted_once() {
+        let app = router();
+        play(&app, &COOKIE_WINS).await;
+        for _ in 0..3 {
+            send(&app, get("/12/board")).await;
+        }
+        let expected = serde_json::json!({"cookie_wins": 1, "milk_wins": 0, "draws": 0});
+        assert_eq!(stats(&app).await, expected);
+        send(&app, post("/12/reset")).await;
+        assert_eq!(stats(&app).await, expected);
+        send(&app, post("/12/reset-stats")).await;
+        assert_eq!(
+            stats(&app).await,
+            serde_json::json!({"cookie_wins": 0, "milk_wins": 0, "draws": 0})
+        );
+    }
+
+    /// Fills the 4x4 board column by column without ever lining up four.
+    const DRAW: [(&str, usize); 16] = [
+        ("milk", 1),
+        ("cookie", 1),
+        ("milk", 1),
+        ("cookie", 1),
+        ("milk", 2),
+        ("cookie", 2),
+        ("milk", 2),
+        ("cookie", 2),
+        ("cookie", 3),
+        ("milk", 3),
+        ("cookie", 3),
+        ("milk", 3),
+        ("cookie", 4),
+        ("milk", 4),
+        ("cookie", 4),
+        ("milk", 4),
+    ];
+
+    async fn status(app: &Router) -> serde_json::Value {
+        serde_json::from_str(&send(app, get("/12/status")).await.2).unwrap()
+    }
+
+    #[tokio::test]
+    async fn status_reports_each_terminal_condition() {
+        let app = router();
+        assert_eq!(
+            status(&app).await,
+            serde_json::json!({"finished": false, "winner": null, "board_full": false})
+        );
+        play(&app, &COOKIE_WINS).await;
+        assert_eq!(
+            status(&app).await,
+            serde_json::json!({"finished": true, "winner": "cookie", "board_full": false})
+        );
+        send(&app, post("/12/reset")).await;
+        play(&app, &DRAW).await;
+        assert_eq!(
+            status(&app).await,
+            serde_json::json!({"finished": true, "winner": null, "board_full": true})
+        );
+    }
+
+    #[tokio::test]
+    async fn refused_moves_say_how_the_game_ended() {
+        let app = router();
+        play(&app, &COOKIE_WINS).await;
+        let (status, headers, _) = send(&app, post("/12/place/milk/3")).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(headers[X_GAME_END], "winner");
+
+        send(&app, post("/12/reset")).await;
+        play(&app, &DRAW).await;
+        let (status, headers, _) = send(&app, post("/12/place/milk/3")).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(headers[X_GAME_END], "full");
+    }
+
+    #[tokio::test]
+    async fn a_full_column_is_refused_without_an_end() {
+        let app = router();
+        play(&app, &DRAW[..4]).await;
+        let (status, headers, _) = send(&app, post("/12/place/milk/1")).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!headers.contains_key(X_GAME_END));
+    }
+
+    #[tokio::test]
+    async fn ascii_charset_draws_a_known_board() {
+        let app = router();
+        let (_, _, body) = play(&app, &[("cookie", 1), ("milk", 1), ("cookie", 4)]).await;
+        assert_eq!(body, "#....#\n#....#\n#M...#\n#C..C#\n######\n");
+        let (_, _, body) = send(&app, get("/12/board?charset=ascii")).await;
+        assert_eq!(body, "#....#\n#....#\n#M...#\n#C..C#\n######\n");
+        let (_, _, body) = send(&app, get("/12/board")).await;
+        assert!(body.starts_with("⬜⬛⬛⬛⬛⬜"), "{body}");
+        send(&app, post("/12/reset")).await;
+        let (_, _, body) = play(&app, &COOKIE_WINS).await;
+        assert!(body.ends_with("C wins!\n"), "{body}");
+    }
+
+    #[tokio::test]
+    async fn simulate_leaves_the_live_game_alone() {
+        let app = router();
+        play(&app, &[("cookie", 2)]).await;
+        let (_, _, before) = send(&app, get("/12/board")).await;
+        let (status, _, preview) = send(&app, post("/12/simulate/milk/2?charset=ascii")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(preview, "#....#\n#....#\n#.M..#\n#.C..#\n######\n");
+        assert_eq!(send(&app, get("/12/board")).await.2, before);
+        assert_eq!(history(&app).await.as_array().unwrap().len(), 1);
+
+        let (status, _, _) = send(&app, post("/12/simulate/tea/2")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _, _) = send(&app, post("/12/simulate/milk/5")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        send(&app, post("/12/reset")).await;
+        play(&app, &COOKIE_WINS).await;
+        let (status, _, _) = send(&app, post("/12/simulate/milk/3")).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    async fn json_board(app: &Router) -> serde_json::Value {
+        let (_, _, body) = send(app, get("/12/board?format=json")).await;
+        serde_json::from_str(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn columns_full_flags_a_filled_column() {
+        let app = router();
+        assert_eq!(
+            json_board(&app).await["columns_full"],
+            serde_json::json!([false, false, false, false])
+        );
+        play(&app, &DRAW[4..8]).await;
+        assert_eq!(
+            json_board(&app).await["columns_full"],
+            serde_json::json!([false, true, false, false])
+        );
+    }
+
+    #[tokio::test]
+    async fn placed_at_names_the_bottom_most_empty_row() {
+        let app = router();
+        let (_, headers, _) = play(&app, &[("cookie", 3)]).await;
+        assert_eq!(headers[X_PLACED_AT], "3,3");
+        let (_, headers, _) = play(&app, &[("milk", 3)]).await;
+        assert_eq!(headers[X_PLACED_AT], "2,3");
+
+        play(&app, &DRAW[..4]).await;
+        let (status, headers, _) = send(&app, post("/12/place/milk/1")).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!headers.contains_key(X_PLACED_AT));
+    }
+
+    #[tokio::test]
+    async fn board_version_is_stable_until_a_move() {
+        let app = router();
+        let (_, first, _) = send(&app, get("/12/board")).await;
+        let (_, second, _) = send(&app, get("/12/board")).await;
+        assert_eq!(first[X_BOARD_VERSION], second[X_BOARD_VERSION]);
+
+        let version = first[X_BOARD_VERSION].clone();
+        let mut poll = get("/12/board");
+        poll.headers_mut().insert(IF_BOARD_VERSION, version.clone());
+        let (status, _, body) = send(&app, poll).await;
+        assert_eq!(status, StatusCode::NOT_MODIFIED);
+        assert!(body.is_empty());
+
+        play(&app, &[("cookie", 1)]).await;
+        let mut poll = get("/12/board");
+        poll.headers_mut().insert(IF_BOARD_VERSION, version.clone());
+        let (status, headers, _) = send(&app, poll).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_ne!(headers[X_BOARD_VERSION], version);
+    }
+}
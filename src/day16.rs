@@ -0,0 +1,691 @@
+use std::{env, fs, sync::Arc};
+
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+};
+use base64::prelude::*;
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, decode_header, encode,
+    errors::ErrorKind,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tracing::*;
+
+use crate::error::AppError;
+
+pub fn router() -> Router {
+    day_16_routes(Day16AppState::from_env())
+}
+
+fn day_16_routes(state: Day16AppState) -> Router {
+    Router::new()
+        .route("/16/decode", post(day_16_decode))
+        .route("/16/verify", post(day_16_verify))
+        .route("/16/wrap", post(day_16_wrap))
+        .route("/16/unwrap", get(day_16_unwrap))
+        .with_state(Arc::new(state))
+}
+
+struct Day16AppState {
+    /// Gift signing secrets; never empty, the first one is the default.
+    secrets: Vec<WrapSecret>,
+    /// Santa's public keys, tried in order by `/16/decode`.
+    keyring: Vec<DecodingKey>,
+    /// Marks the gift cookie `Secure`; off by default since local dev is plain http.
+    secure_cookie: bool,
+}
+
+/// A gift signing secret, named by `kid` in the tokens it signs.
+struct WrapSecret {
+    kid: Option<String>,
+    secret: Vec<u8>,
+}
+
+impl Day16AppState {
+    fn new(secrets: Vec<WrapSecret>, keyring: Vec<DecodingKey>) -> Self {
+        assert!(!secrets.is_empty(), "at least one wrap secret is required");
+        Self {
+            secrets,
+            keyring,
+            secure_cookie: false,
+        }
+    }
+
+    fn from_env() -> Self {
+        let secrets = day_16_load_secrets();
+        let secure_cookie = match env::var("GIFT_COOKIE_SECURE").as_deref() {
+            Ok("1" | "true") => true,
+            Ok("0" | "false") | Err(_) => false,
+            Ok(value) => {
+                warn!("invalid value {value:?} for GIFT_COOKIE_SECURE, using false");
+                false
+            }
+        };
+        Self {
+            secure_cookie,
+            ..Self::new(secrets, day_16_load_keyring())
+        }
+    }
+
+    /// The secret named `kid`, or the default one without a `kid`.
+    fn wrap_secret(&self, kid: Option<&str>) -> Option<&WrapSecret> {
+        match kid {
+            Some(kid) => self
+                .secrets
+                .iter()
+                .find(|secret| secret.kid.as_deref() == Some(kid)),
+            None => self.secrets.first(),
+        }
+    }
+}
+
+/// Reads the `kid:secret` pairs in the comma-separated `JWT_SECRETS`, for key
+/// rotation. Without any, gifts are signed with the single
+/// `JWT_SIGNING_SECRET` and carry no `kid`.
+fn day_16_load_secrets() -> Vec<WrapSecret> {
+    let mut secrets = Vec::<WrapSecret>::new();
+    if let Ok(pairs) = env::var("JWT_SECRETS") {
+        for pair in pairs
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+        {
+            match pair.split_once(':') {
+                Some((kid, secret)) if !kid.is_empty() && !secret.is_empty() => {
+                    if secrets
+                        .iter()
+                        .any(|known| known.kid.as_deref() == Some(kid))
+                    {
+                        warn!("skipping duplicate kid {kid:?} in JWT_SECRETS");
+                        continue;
+                    }
+                    secrets.push(WrapSecret {
+                        kid: Some(kid.to_string()),
+                        secret: secret.as_bytes().to_vec(),
+                    });
+                }
+                _ => warn!("skipping invalid entry in JWT_SECRETS, expected kid:secret"),
+            }
+        }
+    }
+    if !secrets.is_empty() {
+        return secrets;
+    }
+    let secret = match env::var("JWT_SIGNING_SECRET") {
+        Ok(secret) => secret.into_bytes(),
+        Err(_) => {
+            warn!("JWT_SIGNING_SECRET is not set, signing gifts with the default secret");
+            DEFAULT_WRAP_SECRET.to_vec()
+        }
+    };
+    vec![WrapSecret { kid: None, secret }]
+}
+
+/// Loads every PEM public key in `SANTA_KEY_DIR`, followed by the embedded key.
+fn day_16_load_keyring() -> Vec<DecodingKey> {
+    let mut keyring = Vec::new();
+    if let Ok(dir) = env::var("SANTA_KEY_DIR") {
+        match fs::read_dir(&dir) {
+            Ok(entries) => {
+                let mut paths = entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "pem"))
+                    .collect::<Vec<_>>();
+                paths.sort();
+                for path in paths {
+                    let key = fs::read(&path).ok().and_then(|pem| {
+                        DecodingKey::from_rsa_pem(&pem)
+                            .or_else(|_| DecodingKey::from_ec_pem(&pem))
+                            .or_else(|_| DecodingKey::from_ed_pem(&pem))
+                            .ok()
+                    });
+                    match key {
+                        Some(key) => keyring.push(key),
+                        None => warn!("skipping unreadable public key {}", path.display()),
+                    }
+                }
+            }
+            Err(err) => warn!("error reading SANTA_KEY_DIR {dir}: {:?}", err),
+        }
+    }
+    keyring.push(DecodingKey::from_rsa_pem(KEY).expect("embedded Santa key is a valid RSA PEM"));
+    keyring
+}
+
+const KEY: &[u8] = include_bytes!("../key/day16_santa_public_key.pem");
+const DEFAULT_WRAP_SECRET: &[u8] = b"secret";
+const WRAP_ALGORITHMS: [Algorithm; 3] = [Algorithm::HS256, Algorithm::HS384, Algorithm::HS512];
+
+/// Why a token failed verification against the keyring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Day16VerifyError {
+    Malformed,
+    InvalidSignature,
+    Expired,
+}
+
+/// Verifies `token` against every key in the keyring, returning its header
+/// and claims. An `exp` claim is only enforced with `check_exp`; it is never
+/// required.
+fn day_16_verify_token(
+    state: &Day16AppState,
+    token: &str,
+    check_exp: bool,
+) -> Result<(Header, Value), Day16VerifyError> {
+    let header = decode_header(token).map_err(|err| {
+        warn!("error decoding header: {:?}", err);
+        Day16VerifyError::Malformed
+    })?;
+    let mut validation = Validation::new(header.alg);
+    validation.required_spec_claims.clear();
+    validation.validate_exp = check_exp;
+    // as in unwrap, an `aud` claim is just data, there is no audience to check
+    validation.validate_aud = false;
+    // keys of another family report `InvalidAlgorithm`; if no key of the
+    // token's family exists at all the token is rejected as malformed
+    let mut verified_against_any = false;
+    for key in &state.keyring {
+        match decode::<Value>(token, key, &validation) {
+            Ok(token) => return Ok((header, token.claims)),
+            Err(err) => match err.kind() {
+                ErrorKind::InvalidSignature => verified_against_any = true,
+                ErrorKind::InvalidAlgorithm => {}
+                ErrorKind::ExpiredSignature => return Err(Day16VerifyError::Expired),
+                _ => return Err(Day16VerifyError::Malformed),
+            },
+        }
+    }
+    if verified_against_any {
+        Err(Day16VerifyError::InvalidSignature)
+    } else {
+        Err(Day16VerifyError::Malformed)
+    }
+}
+
+#[derive(Deserialize)]
+struct Day16DecodeQuery {
+    #[serde(default)]
+    check_exp: bool,
+    /// Answer with `{"header": ..., "claims": ...}` instead of the bare claims.
+    #[serde(default)]
+    include_header: bool,
+}
+
+async fn day_16_decode(
+    State(state): State<Arc<Day16AppState>>,
+    Query(query): Query<Day16DecodeQuery>,
+    body: String,
+) -> (StatusCode, Body) {
+    // the puzzle's tokens may be long expired, so expiry is opt-in
+    match day_16_verify_token(&state, &body, query.check_exp) {
+        Ok((header, claims)) if query.include_header => {
+            let decoded = json!({ "header": header, "claims": claims });
+            (StatusCode::OK, Body::from(decoded.to_string()))
+        }
+        Ok((_, claims)) => (StatusCode::OK, Body::from(claims.to_string())),
+        Err(Day16VerifyError::InvalidSignature) if query.check_exp => {
+            (StatusCode::UNAUTHORIZED, Body::from("invalid signature"))
+        }
+        Err(Day16VerifyError::InvalidSignature) => (StatusCode::UNAUTHORIZED, Body::empty()),
+        Err(Day16VerifyError::Expired) => (StatusCode::UNAUTHORIZED, Body::from("expired")),
+        Err(Day16VerifyError::Malformed) => (StatusCode::BAD_REQUEST, Body::empty()),
+    }
+}
+
+#[derive(Serialize)]
+struct Day16Verification {
+    valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<Day16VerifyError>,
+}
+
+async fn day_16_verify(
+    State(state): State<Arc<Day16AppState>>,
+    body: String,
+) -> Json<Day16Verification> {
+    let reason = day_16_verify_token(&state, body.trim(), true).err();
+    Json(Day16Verification {
+        valid: reason.is_none(),
+        reason,
+    })
+}
+
+#[derive(Deserialize)]
+struct Day16WrapQuery {
+    alg: Option<String>,
+    /// Seconds until the gift expires, added to the payload as `exp`.
+    ttl: Option<u64>,
+    /// Which of `JWT_SECRETS` to sign with; the first one by default.
+    kid: Option<String>,
+}
+
+/// Longest `ttl` a gift may be wrapped with: a year.
+const MAX_WRAP_TTL: u64 = 365 * 24 * 60 * 60;
+
+async fn day_16_wrap(
+    State(state): State<Arc<Day16AppState>>,
+    Query(query): Query<Day16WrapQuery>,
+    Json(mut body): Json<Value>,
+) -> Result<impl IntoResponse, AppError> {
+    // only HMAC algorithms make sense with a shared secret
+    let alg = match query.alg.as_deref() {
+        None | Some("HS256") => Algorithm::HS256,
+        Some("HS384") => Algorithm::HS384,
+        Some("HS512") => Algorithm::HS512,
+        Some(alg) => {
+            return Err(AppError::BadRequest(format!(
+                "unsupported signing algorithm {alg}"
+            )));
+        }
+    };
+    let Some(secret) = state.wrap_secret(query.kid.as_deref()) else {
+        return Err(AppError::BadRequest(format!(
+            "unknown kid {}",
+            query.kid.unwrap_or_default()
+        )));
+    };
+    if let Some(ttl) = query.ttl {
+        if !(1..=MAX_WRAP_TTL).contains(&ttl) {
+            return Err(AppError::BadRequest(format!("ttl {ttl} out of range")));
+        }
+        let Some(claims) = body.as_object_mut() else {
+            return Err(AppError::BadRequest(
+                "ttl needs a JSON object payload".to_string(),
+            ));
+        };
+        if claims.contains_key("exp") {
+            return Err(AppError::Conflict(
+                "ttl given for a payload that already has exp".to_string(),
+            ));
+        }
+        let exp = chrono::Utc::now().timestamp() + ttl as i64;
+        claims.insert("exp".to_string(), exp.into());
+    }
+    let header = Header {
+        kid: secret.kid.clone(),
+        ..Header::new(alg)
+    };
+    let token = encode(&header, &body, &EncodingKey::from_secret(&secret.secret))?;
+
+    let mut cookie = format!("gift={}; HttpOnly; SameSite=Strict; Path=/", token);
+    if state.secure_cookie {
+        cookie.push_str("; Secure");
+    }
+    Ok([("set-cookie", cookie)])
+}
+
+async fn day_16_unwrap(
+    State(state): State<Arc<Day16AppState>>,
+    headers: HeaderMap,
+) -> (StatusCode, Body) {
+    match headers
+        .get("cookie")
+        .and_then(|cookie| cookie.to_str().ok())
+        .and_then(|cookie| cookie.strip_prefix("gift="))
+    {
+        Some(token) => {
+            let header = match decode_header(token) {
+                Ok(header) if WRAP_ALGORITHMS.contains(&header.alg) => header,
+                Ok(header) => {
+                    warn!("unwrap: unexpected algorithm {:?}", header.alg);
+                    return (StatusCode::BAD_REQUEST, Body::empty());
+                }
+                Err(err) => {
+                    warn!("unwrap: error decoding header: {:?}", err);
+                    return (StatusCode::BAD_REQUEST, Body::empty());
+                }
+            };
+            // a kid no longer configured was rotated out, so its gifts are void
+            let Some(secret) = state.wrap_secret(header.kid.as_deref()) else {
+                warn!("unwrap: unknown kid {:?}", header.kid);
+                return (StatusCode::UNAUTHORIZED, Body::empty());
+            };
+            // the payload is an arbitrary gift, so don't treat `aud` as an audience claim
+            let mut validation = Validation::new(header.alg);
+            validation.required_spec_claims.clear();
+            validation.validate_aud = false;
+            if let Err(err) = decode::<Value>(
+                token,
+                &DecodingKey::from_secret(&secret.secret),
+                &validation,
+            ) {
+                warn!("unwrap: error verifying token: {:?}", err);
+                return match err.kind() {
+                    ErrorKind::InvalidSignature | ErrorKind::ExpiredSignature => {
+                        (StatusCode::UNAUTHORIZED, Body::empty())
+                    }
+                    _ => (StatusCode::BAD_REQUEST, Body::empty()),
+                };
+            }
+            // hand back the payload bytes as signed rather than re-serializing the claims
+            let Some(payload) = token.split('.').nth(1) else {
+                return (StatusCode::BAD_REQUEST, Body::empty());
+            };
+            match BASE64_URL_SAFE_NO_PAD.decode(payload.as_bytes()) {
+                Ok(body) => (StatusCode::OK, Body::from(body)),
+                Err(err) => {
+                    warn!("error decoding body: {:?}", err);
+                    (StatusCode::BAD_REQUEST, Body::empty())
+                }
+            }
+        }
+        None => (StatusCode::BAD_REQUEST, Body::empty()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::extract::Request;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    const SANTA: &[u8] = b"santa";
+    const SANTA_OLD: &[u8] = b"santa-old";
+
+    /// An app signing gifts with `secret` whose keyring holds two HMAC keys,
+    /// standing in for Santa's PEM keys.
+    fn app() -> Router {
+        app_with(vec![WrapSecret {
+            kid: None,
+            secret: b"secret".to_vec(),
+        }])
+    }
+
+    fn app_with(secrets: Vec<WrapSecret>) -> Router {
+        let keyring = vec![
+            DecodingKey::from_secret(SANTA),
+            DecodingKey::from_secret(SANTA_OLD),
+        ];
+        day_16_routes(Day16AppState::new(secrets, keyring))
+    }
+
+    fn sign(claims: &Value, secret: &[u8]) -> String {
+        encode(
+            &Header::default(),
+            claims,
+            &EncodingKey::from_secret(secret),
+        )
+        .unwrap()
+    }
+
+    async fn send(app: Router, request: Request) -> (StatusCode, HeaderMap, String) {
+        let response = app.oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        (
+            parts.status,
+            parts.headers,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    async fn post(uri: &str, token: String) -> (StatusCode, String) {
+        let request = Request::post(uri).body(Body::from(token)).unwrap();
+        let (status, _, body) = send(app(), request).await;
+        (status, body)
+    }
+
+    /// Wraps `payload` through `uri`, returning the status and the gift
+    /// token from the cookie.
+    async fn wrap(app: Router, uri: &str, payload: Value) -> (StatusCode, Option<String>) {
+        let request = Request::post(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(payload.to_string()))
+            .unwrap();
+        let (status, headers, _) = send(app, request).await;
+        let token = headers.get("set-cookie").map(|cookie| {
+            let cookie = cookie.to_str().unwrap();
+            let gift = cookie.split(';').next().unwrap();
+            gift.strip_prefix("gift=").unwrap().to_string()
+        });
+        (status, token)
+    }
+
+    #[tokio::test]
+    async fn wrap_signs_with_the_requested_algorithm() {
+        for (query, alg) in [
+            ("", Algorithm::HS256),
+            ("?alg=HS256", Algorithm::HS256),
+            ("?alg=HS384", Algorithm::HS384),
+            ("?alg=HS512", Algorithm::HS512),
+        ] {
+            let uri = format!("/16/wrap{query}");
+            let (status, token) = wrap(app(), &uri, json!({ "gift": "coal" })).await;
+            assert_eq!(status, StatusCode::OK, "{query}");
+            assert_eq!(decode_header(&token.unwrap()).unwrap().alg, alg, "{query}");
+        }
+        for alg in ["RS256", "none", "HS1024"] {
+            let uri = format!("/16/wrap?alg={alg}");
+            let (status, token) = wrap(app(), &uri, json!({ "gift": "coal" })).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{alg}");
+            assert!(token.is_none());
+        }
+    }
+
+    async fn unwrap(app: Router, token: Option<&str>) -> (StatusCode, String) {
+        let mut request = Request::get("/16/unwrap");
+        if let Some(token) = token {
+            request = request.header("cookie", format!("gift={token}"));
+        }
+        let (status, _, body) = send(app, request.body(Body::empty()).unwrap()).await;
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn unwrap_returns_the_wrapped_payload() {
+        let payload = json!({ "gift": "sled", "wrapping": ["red", "gold"] });
+        let (_, token) = wrap(app(), "/16/wrap", payload.clone()).await;
+        let (status, body) = unwrap(app(), token.as_deref()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap(), payload);
+    }
+
+    #[tokio::test]
+    async fn unwrap_rejects_a_tampered_payload() {
+        let (_, token) = wrap(app(), "/16/wrap", json!({ "gift": "coal" })).await;
+        let token = token.unwrap();
+        let mut parts = token.split('.').collect::<Vec<_>>();
+        let forged = BASE64_URL_SAFE_NO_PAD.encode(json!({ "gift": "pony" }).to_string());
+        parts[1] = &forged;
+        let (status, body) = unwrap(app(), Some(&parts.join("."))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body, "");
+    }
+
+    #[tokio::test]
+    async fn unwrap_needs_a_gift_cookie() {
+        assert_eq!(unwrap(app(), None).await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            unwrap(app(), Some("not-a-token")).await.0,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn gifts_are_signed_with_the_configured_secret() {
+        let custom = || {
+            app_with(vec![WrapSecret {
+                kid: None,
+                secret: b"north-pole".to_vec(),
+            }])
+        };
+        let payload = json!({ "gift": "scarf" });
+        let (_, token) = wrap(custom(), "/16/wrap", payload.clone()).await;
+        let token = token.unwrap();
+        let mut validation = Validation::default();
+        validation.required_spec_claims.clear();
+        let key = DecodingKey::from_secret(b"north-pole");
+        assert_eq!(
+            decode::<Value>(&token, &key, &validation).unwrap().claims,
+            payload
+        );
+
+        let (status, body) = unwrap(custom(), Some(&token)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap(), payload);
+        // the default secret doesn't open it
+        assert_eq!(
+            unwrap(app(), Some(&token)).await.0,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn decode_tries_every_key_in_the_keyring() {
+        let claims = json!({ "reindeer": "Rudolph" });
+        for secret in [SANTA, SANTA_OLD] {
+            let (status, body) = post("/16/decode", sign(&claims, secret)).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(serde_json::from_str::<Value>(&body).unwrap(), claims);
+        }
+        let (status, _) = post("/16/decode", sign(&claims, b"grinch")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = post("/16/decode", "not a token".to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn decode_accepts_an_aud_claim() {
+        let claims = json!({ "aud": "elves", "gift": "socks" });
+        let (status, body) = post("/16/decode", sign(&claims, SANTA)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap(), claims);
+
+        let (_, body) = post("/16/verify", sign(&claims, SANTA)).await;
+        assert_eq!(body, r#"{"valid":true}"#);
+    }
+
+    #[tokio::test]
+    async fn expiry_is_only_checked_on_request() {
+        let expired = json!({ "gift": "eggnog", "exp": chrono::Utc::now().timestamp() - 3600 });
+        let (status, body) = post("/16/decode", sign(&expired, SANTA)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap(), expired);
+
+        let (status, body) = post("/16/decode?check_exp=true", sign(&expired, SANTA)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body, "expired");
+
+        let forged = sign(&json!({ "gift": "eggnog" }), b"grinch");
+        let (status, body) = post("/16/decode?check_exp=true", forged).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body, "invalid signature");
+    }
+
+    #[tokio::test]
+    async fn wrap_ttl_sets_exp() {
+        let before = chrono::Utc::now().timestamp();
+        let (status, token) = wrap(app(), "/16/wrap?ttl=3600", json!({ "gift": "mug" })).await;
+        assert_eq!(status, StatusCode::OK);
+        let payload = token.unwrap().split('.').nth(1).unwrap().to_string();
+        let claims: Value =
+            serde_json::from_slice(&BASE64_URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap();
+        let exp = claims["exp"].as_i64().unwrap();
+        let after = chrono::Utc::now().timestamp();
+        assert!((before + 3600..=after + 3600).contains(&exp), "{exp}");
+        assert_eq!(claims["gift"], "mug");
+    }
+
+    #[tokio::test]
+    async fn wrap_rejects_an_unusable_ttl() {
+        let with_exp = json!({ "gift": "mug", "exp": 1 });
+        let (status, _) = wrap(app(), "/16/wrap?ttl=60", with_exp).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        for uri in ["/16/wrap?ttl=0", "/16/wrap?ttl=31536001"] {
+            let (status, _) = wrap(app(), uri, json!({ "gift": "mug" })).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        }
+        let (status, _) = wrap(app(), "/16/wrap?ttl=60", json!(["mug"])).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        // a year is still fine
+        let (status, _) = wrap(app(), "/16/wrap?ttl=31536000", json!({})).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    async fn wrap_cookie(state: Day16AppState) -> String {
+        let request = Request::post("/16/wrap")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"gift":"hat"}"#))
+            .unwrap();
+        let (_, headers, _) = send(day_16_routes(state), request).await;
+        headers["set-cookie"].to_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn gift_cookie_has_safe_attributes() {
+        let secret = || WrapSecret {
+            kid: None,
+            secret: b"secret".to_vec(),
+        };
+        let cookie = wrap_cookie(Day16AppState::new(vec![secret()], vec![])).await;
+        let attributes = cookie.split("; ").skip(1).collect::<Vec<_>>();
+        assert!(cookie.starts_with("gift=ey"), "{cookie}");
+        assert_eq!(attributes, ["HttpOnly", "SameSite=Strict", "Path=/"]);
+
+        let state = Day16AppState {
+            secure_cookie: true,
+            ..Day16AppState::new(vec![secret()], vec![])
+        };
+        let cookie = wrap_cookie(state).await;
+        assert!(cookie.ends_with("; Path=/; Secure"), "{cookie}");
+    }
+
+    #[tokio::test]
+    async fn decode_can_include_the_header() {
+        let claims = json!({ "gift": "book" });
+        let (status, body) = post("/16/decode?include_header=true", sign(&claims, SANTA)).await;
+        assert_eq!(status, StatusCode::OK);
+        let decoded: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(decoded["header"]["alg"], "HS256");
+        assert_eq!(decoded["claims"], claims);
+    }
+
+    #[tokio::test]
+    async fn wrap_and_unwrap_under_a_non_default_kid() {
+        let rotating = |kids: &[&str]| {
+            app_with(
+                kids.iter()
+                    .map(|kid| WrapSecret {
+                        kid: Some(kid.to_string()),
+                        secret: format!("secret-{kid}").into_bytes(),
+                    })
+                    .collect(),
+            )
+        };
+        let payload = json!({ "gift": "kite" });
+        let (status, token) = wrap(
+            rotating(&["new", "old"]),
+            "/16/wrap?kid=old",
+            payload.clone(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let token = token.unwrap();
+        assert_eq!(decode_header(&token).unwrap().kid.as_deref(), Some("old"));
+        let (status, body) = unwrap(rotating(&["new", "old"]), Some(&token)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap(), payload);
+
+        // without a kid the first secret signs
+        let (_, token) = wrap(rotating(&["new", "old"]), "/16/wrap", payload.clone()).await;
+        assert_eq!(
+            decode_header(&token.unwrap()).unwrap().kid.as_deref(),
+            Some("new")
+        );
+
+        let (status, _) = wrap(rotating(&["new"]), "/16/wrap?kid=old", payload).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        // once "old" is rotated out its gifts no longer open
+        let (_, token) = wrap(rotating(&["new", "old"]), "/16/wrap?kid=old", json!({})).await;
+        let (status, _) = unwrap(rotating(&["new"]), token.as_deref()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,2691 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::{
+        ConnectInfo, FromRequest, Path, Query, Request, State,
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+    },
+    http::{
+        HeaderMap, StatusCode,
+        header::{
+            AUTHORIZATION, CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH, LOCATION, WWW_AUTHENTICATE,
+        },
+    },
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+};
+use axum_macros::{FromRequest, FromRequestParts};
+use futures_util::{StreamExt, stream};
+use leaky_bucket::RateLimiter;
+use rand::{Rng, SeedableRng, distributions::Alphanumeric, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+use sqlx::{Acquire, PgPool, prelude::FromRow, types::uuid};
+use tracing::*;
+
+pub fn router(pool: PgPool) -> Router {
+    day_19_routes(Day19AppState::from_env(pool))
+}
+
+fn day_19_routes(state: Day19AppState) -> Router {
+    let state = Arc::new(state);
+    // only writes are throttled; reads stay unlimited
+    let writes = Router::new()
+        .route("/19/remove/{id}", delete(day_19_remove))
+        .route("/19/remove-batch", post(day_19_remove_batch))
+        .route("/19/restore/{id}", post(day_19_restore))
+        .route("/19/purge", post(day_19_purge))
+        .route("/19/undo/{id}", put(day_19_undo))
+        .route("/19/draft", post(day_19_draft))
+        .route("/19/import", post(day_19_import))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            day_19_write_limit,
+        ));
+    // checked before the write limit so rejected callers don't spend budget
+    let guarded = Router::new()
+        .route("/19/reset", post(day_19_reset))
+        .merge(writes)
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            day_19_require_token,
+        ));
+    Router::new()
+        .route("/19/cite/{id}", get(day_19_cite))
+        .route("/19/cite/{id}/version/{version}", get(day_19_cite_version))
+        .route("/19/list", get(day_19_list))
+        .route("/19/author/{author}/list", get(day_19_author_list))
+        .route("/19/search", get(day_19_search))
+        .route("/19/export", get(day_19_export))
+        .route("/19/count", get(day_19_count))
+        .route("/19/authors", get(day_19_authors))
+        .route("/19/random", get(day_19_random))
+        .route("/19/recent", get(day_19_recent))
+        .merge(guarded)
+        .with_state(state)
+}
+
+#[derive(Deserialize)]
+struct QuotePost {
+    author: String,
+    quote: String,
+}
+
+/// A `QuotePost` body whose shape is checked by hand, so a missing or mistyped
+/// field is a 400 naming it instead of axum's plain-text rejection.
+struct QuoteBody(QuotePost);
+
+impl<S: Send + Sync> FromRequest<S> for QuoteBody {
+    type Rejection = Day19Error;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let invalid = |error: String| Day19Error {
+            status: StatusCode::BAD_REQUEST,
+            code: "invalid_body",
+            error,
+        };
+        let Json(value) = Json::<serde_json::Value>::from_request(request, state).await?;
+        if !value.is_object() {
+            return Err(invalid("body must be a JSON object".to_string()));
+        }
+        for name in ["author", "quote"] {
+            match value.get(name) {
+                None | Some(serde_json::Value::Null) => {
+                    return Err(invalid(format!("missing field: {name}")));
+                }
+                Some(serde_json::Value::String(_)) => {}
+                Some(_) => return Err(invalid(format!("field {name} must be a string"))),
+            }
+        }
+        serde_json::from_value(value)
+            .map(QuoteBody)
+            .map_err(|err| invalid(err.to_string()))
+    }
+}
+
+const MAX_AUTHOR_LEN: usize = 256;
+const MAX_QUOTE_LEN: usize = 4096;
+
+impl QuotePost {
+    /// Checks the fields before they reach the database.
+    fn validate(&self) -> Result<(), String> {
+        for (name, value, max_len) in [
+            ("author", &self.author, MAX_AUTHOR_LEN),
+            ("quote", &self.quote, MAX_QUOTE_LEN),
+        ] {
+            if value.trim().is_empty() {
+                return Err(format!("{name} must not be empty"));
+            }
+            if value.chars().count() > max_len {
+                return Err(format!("{name} must be at most {max_len} characters"));
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Day19AppState {
+    pool: PgPool,
+    write_limiter: WriteLimiter,
+    /// Mints pagination tokens.
+    token_rng: Mutex<StdRng>,
+    /// Bearer token required on writes; `None` leaves them open.
+    api_token: Option<String>,
+    /// Ids for new quotes, random v4 UUIDs outside of tests.
+    quote_ids: Box<dyn Fn() -> uuid::Uuid + Send + Sync>,
+}
+
+impl Day19AppState {
+    /// Default limits, an entropy-seeded RNG, random ids and open writes.
+    fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            write_limiter: WriteLimiter::new(WriteLimitConfig::default()),
+            token_rng: Mutex::new(StdRng::from_entropy()),
+            api_token: None,
+            quote_ids: Box::new(uuid::Uuid::new_v4),
+        }
+    }
+
+    fn from_env(pool: PgPool) -> Self {
+        Self {
+            write_limiter: WriteLimiter::new(WriteLimitConfig::from_env()),
+            token_rng: Mutex::new(day_19_token_rng()),
+            api_token: day_19_api_token(),
+            ..Self::new(pool)
+        }
+    }
+}
+
+/// Reads `API_TOKEN`; unset or empty disables write authentication.
+fn day_19_api_token() -> Option<String> {
+    match env::var("API_TOKEN") {
+        Ok(token) if token.is_empty() => {
+            warn!("empty API_TOKEN, day 19 writes are unauthenticated");
+            None
+        }
+        Ok(token) => Some(token),
+        Err(_) => None,
+    }
+}
+
+/// Seeds the token RNG from `QUOTE_TOKEN_SEED` for reproducible tokens, or
+/// from entropy otherwise.
+fn day_19_token_rng() -> StdRng {
+    match env::var("QUOTE_TOKEN_SEED").map(|seed| seed.parse::<u64>()) {
+        Ok(Ok(seed)) => StdRng::seed_from_u64(seed),
+        Ok(Err(_)) => {
+            warn!("invalid QUOTE_TOKEN_SEED, seeding pagination tokens from entropy");
+            StdRng::from_entropy()
+        }
+        Err(_) => StdRng::from_entropy(),
+    }
+}
+
+/// Per-client write budget, overridable through `QUOTE_WRITE_BURST` and
+/// `QUOTE_WRITE_REFILL_MS`.
+#[derive(Clone, Copy)]
+struct WriteLimitConfig {
+    burst: usize,
+    interval: Duration,
+}
+
+impl Default for WriteLimitConfig {
+    fn default() -> Self {
+        Self {
+            burst: 10,
+            interval: Duration::from_millis(500),
+        }
+    }
+}
+
+impl WriteLimitConfig {
+    fn from_env() -> Self {
+        let defaults = Self::default();
+        let positive = |name: &str, default: u64| match env::var(name) {
+            Ok(value) => match value.parse() {
+                Ok(0) | Err(_) => {
+                    warn!("invalid value {value:?} for {name}, using default");
+                    default
+                }
+                Ok(value) => value,
+            },
+            Err(_) => default,
+        };
+        Self {
+            burst: positive("QUOTE_WRITE_BURST", defaults.burst as u64) as usize,
+            interval: Duration::from_millis(positive(
+                "QUOTE_WRITE_REFILL_MS",
+                defaults.interval.as_millis() as u64,
+            )),
+        }
+    }
+}
+
+/// One leaky bucket per client address, with the time it was last used.
+struct WriteLimiter {
+    config: WriteLimitConfig,
+    buckets: Mutex<HashMap<IpAddr, (RateLimiter, Instant)>>,
+}
+
+impl WriteLimiter {
+    fn new(config: WriteLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn try_acquire(&self, client: IpAddr) -> bool {
+        let mut buckets = self.buckets.lock().unwrap();
+        let now = Instant::now();
+        if !buckets.contains_key(&client) {
+            // a bucket idle long enough to refill completely is the same as a new one
+            let refill_time = self.config.interval * self.config.burst as u32;
+            buckets.retain(|_, (_, used)| now.duration_since(*used) < refill_time);
+        }
+        let (limiter, used) = buckets.entry(client).or_insert_with(|| {
+            let limiter = RateLimiter::builder()
+                .max(self.config.burst)
+                .initial(self.config.burst)
+                .interval(self.config.interval)
+                .build();
+            (limiter, now)
+        });
+        *used = now;
+        limiter.try_acquire(1)
+    }
+}
+
+/// The client address, preferring `X-Forwarded-For` since the platform proxy
+/// is the peer of every connection. Only the right-most hop is used: the proxy
+/// appends it, while anything before it is whatever the client sent.
+fn client_ip(request: &Request) -> IpAddr {
+    request
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit(',').next()?.trim().parse().ok())
+        .or_else(|| {
+            request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip())
+        })
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+}
+
+async fn day_19_write_limit(
+    State(state): State<Arc<Day19AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client = client_ip(&request);
+    if !state.write_limiter.try_acquire(client) {
+        warn!("write limit exceeded for {client}");
+        return day_19_error(
+            StatusCode::TOO_MANY_REQUESTS,
+            "rate_limited",
+            "too many writes, slow down",
+        );
+    }
+    next.run(request).await
+}
+
+/// Compares without short-circuiting so the response time doesn't leak how
+/// much of the token was right.
+fn day_19_token_matches(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len()
+        && given
+            .iter()
+            .zip(expected)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn day_19_require_token(
+    State(state): State<Arc<Day19AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(expected) = &state.api_token else {
+        return next.run(request).await;
+    };
+    let given = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let authorized = given
+        .is_some_and(|token| day_19_token_matches(token.trim().as_bytes(), expected.as_bytes()));
+    if !authorized {
+        warn!(
+            "rejected unauthenticated write from {}",
+            client_ip(&request)
+        );
+        let mut response = day_19_error(
+            StatusCode::UNAUTHORIZED,
+            "unauthorized",
+            "missing or wrong bearer token",
+        );
+        response
+            .headers_mut()
+            .insert(WWW_AUTHENTICATE, "Bearer".parse().unwrap());
+        return response;
+    }
+    next.run(request).await
+}
+
+/// Body of every day 19 error response without a more useful payload.
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+    /// Stable machine-readable tag, e.g. `not_found`.
+    code: &'static str,
+}
+
+/// Any failed day 19 request. Renders as a JSON `ErrorBody`, so database
+/// failures and rejected extractors look like every other error.
+struct Day19Error {
+    status: StatusCode,
+    code: &'static str,
+    error: String,
+}
+
+impl IntoResponse for Day19Error {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            error: self.error,
+            code: self.code,
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+impl From<sqlx::Error> for Day19Error {
+    fn from(err: sqlx::Error) -> Self {
+        error!("database error: {err}");
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            code: "internal",
+            error: "database error".to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for Day19Error {
+    fn from(err: serde_json::Error) -> Self {
+        error!("json serialization: {err}");
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            code: "internal",
+            error: "failed to serialize response".to_string(),
+        }
+    }
+}
+
+impl From<JsonRejection> for Day19Error {
+    fn from(rejection: JsonRejection) -> Self {
+        Self {
+            status: rejection.status(),
+            code: "invalid_body",
+            error: rejection.body_text(),
+        }
+    }
+}
+
+impl From<PathRejection> for Day19Error {
+    fn from(rejection: PathRejection) -> Self {
+        Self {
+            status: rejection.status(),
+            code: "invalid_path",
+            error: rejection.body_text(),
+        }
+    }
+}
+
+impl From<QueryRejection> for Day19Error {
+    fn from(rejection: QueryRejection) -> Self {
+        Self {
+            status: rejection.status(),
+            code: "invalid_query",
+            error: rejection.body_text(),
+        }
+    }
+}
+
+/// `Json`, `Path` and `Query` whose rejections are `Day19Error`s.
+#[derive(FromRequest)]
+#[from_request(via(Json), rejection(Day19Error))]
+struct Day19Json<T>(T);
+
+#[derive(FromRequestParts)]
+#[from_request(via(Path), rejection(Day19Error))]
+struct Day19Path<T>(T);
+
+#[derive(FromRequestParts)]
+#[from_request(via(Query), rejection(Day19Error))]
+struct Day19Query<T>(T);
+
+fn day_19_error(status: StatusCode, code: &'static str, error: impl Into<String>) -> Response {
+    Day19Error {
+        status,
+        code,
+        error: error.into(),
+    }
+    .into_response()
+}
+
+fn day_19_not_found() -> Response {
+    day_19_error(StatusCode::NOT_FOUND, "not_found", "quote not found")
+}
+
+fn day_19_internal(error: &str) -> Response {
+    day_19_error(StatusCode::INTERNAL_SERVER_ERROR, "internal", error)
+}
+
+#[derive(Deserialize, Serialize, FromRow)]
+struct Quote {
+    id: uuid::Uuid,
+    author: String,
+    quote: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+    version: i32,
+}
+
+impl Quote {
+    /// Weak ETag identifying this revision of the quote.
+    fn etag(&self) -> String {
+        format!("W/\"{}-{}\"", self.id, self.version)
+    }
+}
+
+/// Whether an `If-None-Match` header matches `etag`, using weak comparison.
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    let Some(value) = headers
+        .get(IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag);
+    value
+        .split(',')
+        .any(|tag| tag.trim() == "*" || opaque(tag) == etag)
+}
+
+async fn day_19_reset(
+    State(state): State<Arc<Day19AppState>>,
+) -> Result<impl IntoResponse, Day19Error> {
+    // all or nothing, so a failed reset never leaves history or keys behind
+    let mut tx = state.pool.begin().await?;
+    sqlx::query("DELETE FROM quotes").execute(&mut *tx).await?;
+    sqlx::query("DELETE FROM quote_history")
+        .execute(&mut *tx)
+        .await?;
+    // keys would only replay to quotes that no longer exist
+    sqlx::query("DELETE FROM idempotency_keys")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok("")
+}
+
+async fn day_19_cite(
+    State(state): State<Arc<Day19AppState>>,
+    Day19Path(id): Day19Path<uuid::Uuid>,
+    headers: HeaderMap,
+) -> Result<Response, Day19Error> {
+    Ok(
+        match sqlx::query_as::<_, Quote>(
+            "SELECT * FROM quotes WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .fetch_one(&state.pool)
+        .await
+        {
+            Ok(quote) => {
+                let etag = quote.etag();
+                if if_none_match(&headers, &etag) {
+                    (StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response()
+                } else {
+                    (
+                        StatusCode::OK,
+                        [(ETAG, etag)],
+                        Body::from(serde_json::to_string(&quote)?),
+                    )
+                        .into_response()
+                }
+            }
+            Err(err) => {
+                warn!("cite: error fetching quote with id {id}: {:?}", err);
+                day_19_not_found()
+            }
+        },
+    )
+}
+
+async fn day_19_cite_version(
+    State(state): State<Arc<Day19AppState>>,
+    Day19Path((id, version)): Day19Path<(uuid::Uuid, i32)>,
+) -> Result<Response, Day19Error> {
+    // the latest revision only lives in `quotes`; a trashed quote hides its history too
+    let quote = sqlx::query_as::<_, Quote>(
+        "SELECT id, author, quote, created_at, version FROM quote_history
+        WHERE id = $1 AND version = $2
+            AND NOT EXISTS (SELECT 1 FROM quotes WHERE id = $1 AND deleted_at IS NOT NULL)
+        UNION ALL
+        SELECT id, author, quote, created_at, version FROM quotes
+        WHERE id = $1 AND version = $2 AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .bind(version)
+    .fetch_optional(&state.pool)
+    .await?;
+    Ok(match quote {
+        Some(quote) => (StatusCode::OK, Body::from(serde_json::to_string(&quote)?)).into_response(),
+        None => day_19_error(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("quote {id} has no version {version}"),
+        ),
+    })
+}
+
+/// Moves a quote to the trash; `/19/restore` brings it back until `/19/purge`.
+async fn day_19_remove(
+    State(state): State<Arc<Day19AppState>>,
+    Day19Path(id): Day19Path<uuid::Uuid>,
+) -> Result<Response, Day19Error> {
+    Ok(
+        match sqlx::query_as::<_, Quote>(
+            "UPDATE quotes SET deleted_at = NOW() WHERE id = $1 AND deleted_at IS NULL RETURNING *",
+        )
+        .bind(id)
+        .fetch_one(&state.pool)
+        .await
+        {
+            Ok(quote) => {
+                (StatusCode::OK, Body::from(serde_json::to_string(&quote)?)).into_response()
+            }
+            Err(err) => {
+                if !matches!(err, sqlx::Error::RowNotFound) {
+                    warn!("Delete row err {:?}", err);
+                    day_19_internal("failed to remove quote")
+                } else {
+                    day_19_not_found()
+                }
+            }
+        },
+    )
+}
+
+/// Takes a quote back out of the trash.
+async fn day_19_restore(
+    State(state): State<Arc<Day19AppState>>,
+    Day19Path(id): Day19Path<uuid::Uuid>,
+) -> Result<Response, Day19Error> {
+    let quote = sqlx::query_as::<_, Quote>(
+        "UPDATE quotes SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL RETURNING *",
+    )
+    .bind(id)
+    .fetch_optional(&state.pool)
+    .await?;
+    Ok(match quote {
+        Some(quote) => (StatusCode::OK, Body::from(serde_json::to_string(&quote)?)).into_response(),
+        None => day_19_error(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            "no trashed quote with that id",
+        ),
+    })
+}
+
+#[derive(Serialize)]
+struct PurgeResult {
+    purged: i64,
+}
+
+/// Permanently deletes every trashed quote along with its history.
+async fn day_19_purge(State(state): State<Arc<Day19AppState>>) -> Result<Response, Day19Error> {
+    let purged = sqlx::query_scalar(
+        "WITH purged AS (
+            DELETE FROM quotes WHERE deleted_at IS NOT NULL RETURNING id
+        ), history AS (
+            DELETE FROM quote_history WHERE id IN (SELECT id FROM purged)
+        )
+        SELECT COUNT(*) FROM purged",
+    )
+    .fetch_one(&state.pool)
+    .await?;
+    let result = PurgeResult { purged };
+    Ok((StatusCode::OK, Body::from(serde_json::to_string(&result)?)).into_response())
+}
+
+const MAX_REMOVE_BATCH_SIZE: usize = 1000;
+
+#[derive(Deserialize)]
+struct RemoveBatch {
+    /// Parsed by hand so a malformed id is a 400 rather than a 422.
+    ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct RemoveBatchResult {
+    removed: usize,
+    not_found: Vec<uuid::Uuid>,
+}
+
+async fn day_19_remove_batch(
+    State(state): State<Arc<Day19AppState>>,
+    Day19Json(batch): Day19Json<RemoveBatch>,
+) -> Result<Response, Day19Error> {
+    if batch.ids.len() > MAX_REMOVE_BATCH_SIZE {
+        return Ok(day_19_error(
+            StatusCode::BAD_REQUEST,
+            "too_many_ids",
+            format!("at most {MAX_REMOVE_BATCH_SIZE} quotes can be removed at once"),
+        ));
+    }
+    let mut ids = Vec::with_capacity(batch.ids.len());
+    for id in &batch.ids {
+        match uuid::Uuid::parse_str(id) {
+            Ok(id) => ids.push(id),
+            Err(_) => {
+                return Ok(day_19_error(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_id",
+                    format!("{id:?} is not a uuid"),
+                ));
+            }
+        }
+    }
+    let removed: Vec<uuid::Uuid> = sqlx::query_scalar(
+        "UPDATE quotes SET deleted_at = NOW()
+            WHERE id = ANY($1) AND deleted_at IS NULL RETURNING id",
+    )
+    .bind(&ids)
+    .fetch_all(&state.pool)
+    .await?;
+    let removed_ids: HashSet<_> = removed.iter().copied().collect();
+    let mut seen = HashSet::new();
+    let not_found = ids
+        .into_iter()
+        .filter(|id| !removed_ids.contains(id) && seen.insert(*id))
+        .collect();
+    let result = RemoveBatchResult {
+        removed: removed.len(),
+        not_found,
+    };
+    Ok((StatusCode::OK, Body::from(serde_json::to_string(&result)?)).into_response())
+}
+
+#[derive(Deserialize)]
+struct UndoQuery {
+    version: Option<i32>,
+}
+
+/// Reads the version a client expects to overwrite, from `If-Match` (either a
+/// bare version or an ETag ending in `-<version>`) or else `?version=`.
+fn expected_version(headers: &HeaderMap, query: &UndoQuery) -> Result<Option<i32>, ()> {
+    match headers.get(IF_MATCH) {
+        Some(value) => value
+            .to_str()
+            .ok()
+            .map(|value| value.trim().trim_start_matches("W/").trim_matches('"'))
+            .and_then(|value| value.rsplit('-').next()?.parse().ok())
+            .map(Some)
+            .ok_or(()),
+        None => Ok(query.version),
+    }
+}
+
+async fn day_19_undo(
+    Day19Path(id): Day19Path<uuid::Uuid>,
+    State(state): State<Arc<Day19AppState>>,
+    Day19Query(query): Day19Query<UndoQuery>,
+    headers: HeaderMap,
+    QuoteBody(quote_post): QuoteBody,
+) -> Result<Response, Day19Error> {
+    if let Err(msg) = quote_post.validate() {
+        return Ok(day_19_error(StatusCode::BAD_REQUEST, "invalid_quote", msg));
+    }
+    let Ok(expected_version) = expected_version(&headers, &query) else {
+        return Ok(day_19_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_version",
+            "If-Match must be a version or quote ETag",
+        ));
+    };
+    Ok(
+        match sqlx::query_as::<_, Quote>(
+            "SELECT * FROM quotes WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .fetch_one(&state.pool)
+        .await
+        {
+            Ok(quote) => {
+                if expected_version.is_some_and(|expected| expected != quote.version) {
+                    return Ok((
+                        StatusCode::CONFLICT,
+                        Body::from(serde_json::to_string(&quote)?),
+                    )
+                        .into_response());
+                }
+                // without an expected version the last write wins; the replaced
+                // revision is archived in the same statement, and the version is
+                // bumped from the locked row so concurrent edits each get their own
+                match sqlx::query_as::<_, Quote>(
+                    "WITH old AS (
+                        SELECT * FROM quotes
+                        WHERE id = $3 AND ($4::INT IS NULL OR version = $4)
+                            AND deleted_at IS NULL
+                        FOR UPDATE
+                    ), archived AS (
+                        INSERT INTO quote_history (id, version, author, quote, created_at)
+                        SELECT id, version, author, quote, created_at FROM old
+                        ON CONFLICT DO NOTHING
+                    )
+                    UPDATE quotes
+                    SET version = old.version + 1, author = $1, quote = $2, updated_at = NOW()
+                    FROM old WHERE quotes.id = old.id
+                    RETURNING quotes.*",
+                )
+                .bind(&quote_post.author)
+                .bind(&quote_post.quote)
+                .bind(id)
+                .bind(expected_version)
+                .fetch_optional(&state.pool)
+                .await
+                {
+                    Ok(None) => {
+                        // another edit landed between our read and write
+                        match sqlx::query_as::<_, Quote>(
+                            "SELECT * FROM quotes WHERE id = $1 AND deleted_at IS NULL",
+                        )
+                        .bind(id)
+                        .fetch_optional(&state.pool)
+                        .await?
+                        {
+                            Some(current) => (
+                                StatusCode::CONFLICT,
+                                Body::from(serde_json::to_string(&current)?),
+                            )
+                                .into_response(),
+                            None => day_19_not_found(),
+                        }
+                    }
+                    Ok(Some(updated)) => {
+                        (StatusCode::OK, Body::from(serde_json::to_string(&updated)?))
+                            .into_response()
+                    }
+                    Err(err) => {
+                        warn!("error updating quote: {:?}", err);
+                        day_19_internal("failed to update quote")
+                    }
+                }
+            }
+            Err(err) => {
+                warn!("undo: error fetching quote with id {id}: {:?}", err);
+                day_19_not_found()
+            }
+        },
+    )
+}
+
+/// How long an `Idempotency-Key` remembers the draft it created.
+const IDEMPOTENCY_KEY_TTL: &str = "24 hours";
+
+/// The draft an `Idempotency-Key` was first used for.
+#[derive(FromRow)]
+struct IdempotentDraft {
+    author: String,
+    quote: String,
+    quote_id: uuid::Uuid,
+}
+
+/// Answers a repeated draft: the original quote if the body matches, or 409
+/// if the key was used for a different quote.
+async fn day_19_replay_draft(
+    pool: &PgPool,
+    key: &str,
+    quote_post: &QuotePost,
+) -> Result<Option<Response>, Day19Error> {
+    let Some(draft) = sqlx::query_as::<_, IdempotentDraft>(
+        "SELECT author, quote, quote_id FROM idempotency_keys WHERE key = $1",
+    )
+    .bind(key)
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+    if draft.author != quote_post.author || draft.quote != quote_post.quote {
+        return Ok(Some(day_19_key_reused()));
+    }
+    Ok(Some(
+        match sqlx::query_as::<_, Quote>(
+            "SELECT * FROM quotes WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(draft.quote_id)
+        .fetch_optional(pool)
+        .await?
+        {
+            Some(quote) => day_19_created(&quote)?,
+            // the quote was removed since, so there's nothing to replay
+            None => day_19_not_found(),
+        },
+    ))
+}
+
+/// 201 for a new quote, with its canonical URL in `Location`.
+fn day_19_created(quote: &Quote) -> Result<Response, Day19Error> {
+    Ok((
+        StatusCode::CREATED,
+        [(LOCATION, format!("/19/cite/{}", quote.id))],
+        Body::from(serde_json::to_string(quote)?),
+    )
+        .into_response())
+}
+
+fn day_19_key_reused() -> Response {
+    day_19_error(
+        StatusCode::CONFLICT,
+        "idempotency_key_reused",
+        "idempotency key was used for a different quote",
+    )
+}
+
+/// How often a draft is inserted with fresh ids before a collision is fatal.
+const DRAFT_INSERT_ATTEMPTS: usize = 3;
+
+/// Whether `err` is Postgres' unique-violation (`23505`).
+fn is_unique_violation(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .is_some_and(|err| err.is_unique_violation())
+}
+
+async fn day_19_draft(
+    State(state): State<Arc<Day19AppState>>,
+    headers: HeaderMap,
+    QuoteBody(quote_post): QuoteBody,
+) -> Result<Response, Day19Error> {
+    if let Err(msg) = quote_post.validate() {
+        return Ok(day_19_error(StatusCode::BAD_REQUEST, "invalid_quote", msg));
+    }
+    let idempotency_key = match headers.get("idempotency-key").map(|key| key.to_str()) {
+        Some(Ok(key)) if !key.is_empty() => Some(key.to_string()),
+        Some(_) => {
+            return Ok(day_19_error(
+                StatusCode::BAD_REQUEST,
+                "invalid_idempotency_key",
+                "Idempotency-Key must be non-empty ASCII",
+            ));
+        }
+        None => None,
+    };
+    if let Some(key) = &idempotency_key {
+        sqlx::query(&format!(
+            "DELETE FROM idempotency_keys WHERE created_at < NOW() - INTERVAL '{IDEMPOTENCY_KEY_TTL}'"
+        ))
+        .execute(&state.pool)
+        .await?;
+        if let Some(replay) = day_19_replay_draft(&state.pool, key, &quote_post).await? {
+            return Ok(replay);
+        }
+    }
+    let mut quote = Quote {
+        id: (state.quote_ids)(),
+        author: quote_post.author.clone(),
+        quote: quote_post.quote.clone(),
+        created_at: chrono::Utc::now(),
+        version: 1,
+    };
+    let mut tx = state.pool.begin().await?;
+    let mut attempt = 1;
+    loop {
+        // a failed statement aborts the transaction, so each try gets a savepoint
+        let mut savepoint = (&mut *tx).begin().await?;
+        match sqlx::query("INSERT INTO quotes (id, author, quote, version) VALUES ($1, $2, $3, $4)")
+            .bind(quote.id)
+            .bind(&quote.author)
+            .bind(&quote.quote)
+            .bind(quote.version)
+            .execute(&mut *savepoint)
+            .await
+        {
+            Ok(_) => {
+                savepoint.commit().await?;
+                break;
+            }
+            Err(err) if is_unique_violation(&err) && attempt < DRAFT_INSERT_ATTEMPTS => {
+                warn!(
+                    "draft: id {} already taken, retrying with a new id",
+                    quote.id
+                );
+                savepoint.rollback().await?;
+                quote.id = (state.quote_ids)();
+                attempt += 1;
+            }
+            Err(err) => {
+                warn!(
+                    "draft: insert quote {} with author {} failed: err {:?}",
+                    quote.quote, quote.author, err
+                );
+                return Ok(day_19_internal("failed to store quote"));
+            }
+        }
+    }
+    if let Some(key) = &idempotency_key {
+        let claimed = sqlx::query(
+            "INSERT INTO idempotency_keys (key, author, quote, quote_id) VALUES ($1, $2, $3, $4)
+            ON CONFLICT (key) DO NOTHING",
+        )
+        .bind(key)
+        .bind(&quote.author)
+        .bind(&quote.quote)
+        .bind(quote.id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            == 1;
+        if !claimed {
+            // a concurrent retry with the same key won the race
+            tx.rollback().await?;
+            return Ok(day_19_replay_draft(&state.pool, key, &quote_post)
+                .await?
+                .unwrap_or_else(day_19_key_reused));
+        }
+    }
+    tx.commit().await?;
+    day_19_created(&quote)
+}
+
+const MAX_IMPORT_SIZE: usize = 1000;
+
+#[derive(Serialize)]
+struct ImportResult {
+    count: usize,
+    ids: Vec<uuid::Uuid>,
+}
+
+async fn day_19_import(
+    State(state): State<Arc<Day19AppState>>,
+    Day19Json(quote_posts): Day19Json<Vec<QuotePost>>,
+) -> Result<Response, Day19Error> {
+    if quote_posts.len() > MAX_IMPORT_SIZE {
+        return Ok(day_19_error(
+            StatusCode::BAD_REQUEST,
+            "too_many_quotes",
+            format!("at most {MAX_IMPORT_SIZE} quotes can be imported at once"),
+        ));
+    }
+    if let Some((index, msg)) = quote_posts
+        .iter()
+        .enumerate()
+        .find_map(|(index, quote_post)| quote_post.validate().err().map(|msg| (index, msg)))
+    {
+        return Ok(day_19_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_quote",
+            format!("invalid quote at index {index}: {msg}"),
+        ));
+    }
+    let mut tx = state.pool.begin().await?;
+    let mut ids = Vec::with_capacity(quote_posts.len());
+    for (index, quote_post) in quote_posts.into_iter().enumerate() {
+        let id = (state.quote_ids)();
+        if let Err(err) = sqlx::query("INSERT INTO quotes (id, author, quote) VALUES ($1, $2, $3)")
+            .bind(id)
+            .bind(&quote_post.author)
+            .bind(&quote_post.quote)
+            .execute(&mut *tx)
+            .await
+        {
+            warn!(
+                "import: insert quote at index {index} failed: err {:?}",
+                err
+            );
+            tx.rollback().await?;
+            return Ok(day_19_error(
+                StatusCode::BAD_REQUEST,
+                "import_failed",
+                format!("failed to import quote at index {index}"),
+            ));
+        }
+        ids.push(id);
+    }
+    tx.commit().await?;
+    let result = ImportResult {
+        count: ids.len(),
+        ids,
+    };
+    Ok((
+        StatusCode::CREATED,
+        Body::from(serde_json::to_string(&result)?),
+    )
+        .into_response())
+}
+
+#[derive(Serialize)]
+struct QuotePage {
+    quotes: Vec<ListedQuote>,
+    page: i64,
+    next_token: Option<String>,
+    /// Quotes matching the listing's filters, across all pages.
+    total_quotes: i64,
+    total_pages: i64,
+}
+
+/// A quote in a listing, possibly cut short by `?preview=`.
+#[derive(Serialize)]
+struct ListedQuote {
+    #[serde(flatten)]
+    quote: Quote,
+    /// Only present for previews, so full listings look as they always did.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    truncated: Option<bool>,
+}
+
+impl ListedQuote {
+    /// Cuts the quote text to `preview` characters plus an ellipsis, if given.
+    fn new(mut quote: Quote, preview: Option<usize>) -> Self {
+        let truncated = preview.map(|preview| match quote.quote.char_indices().nth(preview) {
+            Some((end, _)) => {
+                quote.quote.truncate(end);
+                quote.quote.push('…');
+                true
+            }
+            None => false,
+        });
+        Self { quote, truncated }
+    }
+}
+
+#[derive(Clone, Copy, Default, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+enum SortField {
+    #[default]
+    CreatedAt,
+    Author,
+    Version,
+}
+
+impl SortField {
+    fn column(self) -> &'static str {
+        match self {
+            SortField::CreatedAt => "created_at",
+            SortField::Author => "author",
+            SortField::Version => "version",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+enum SortDir {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortDir {
+    fn keyword(self) -> &'static str {
+        match self {
+            SortDir::Asc => "ASC",
+            SortDir::Desc => "DESC",
+        }
+    }
+
+    /// Comparison selecting the rows that come after a key in this direction.
+    fn after(self) -> &'static str {
+        match self {
+            SortDir::Asc => ">",
+            SortDir::Desc => "<",
+        }
+    }
+}
+
+/// Where the next page of a listing starts and how it is ordered. Minted
+/// cursors live in `pagination_tokens` for `TOKEN_TTL`.
+///
+/// Pages continue after the last row served (keyset pagination), so rows
+/// inserted or removed meanwhile can't shift later pages. `offset` only counts
+/// the rows served so far, for the page number.
+#[derive(Clone, FromRow)]
+struct ListCursor {
+    offset: i64,
+    limit: i64,
+    sort: SortField,
+    dir: SortDir,
+    last_id: Option<uuid::Uuid>,
+    last_created_at: Option<chrono::DateTime<chrono::Utc>>,
+    last_author: Option<String>,
+    last_version: Option<i32>,
+    /// Inclusive bounds on `created_at`, kept so every page applies them.
+    created_after: Option<chrono::DateTime<chrono::Utc>>,
+    created_before: Option<chrono::DateTime<chrono::Utc>>,
+    /// Only quotes by this author, for `/19/author/{author}/list`.
+    author: Option<String>,
+}
+
+impl ListCursor {
+    /// Conditions for the listing's filters, numbering placeholders from
+    /// `first_param`. Values are bound in field order.
+    fn filter_conditions(&self, first_param: usize) -> Vec<String> {
+        let mut conditions = vec!["deleted_at IS NULL".to_string()];
+        let mut next_param = first_param;
+        if self.created_after.is_some() {
+            conditions.push(format!("created_at >= ${next_param}"));
+            next_param += 1;
+        }
+        if self.created_before.is_some() {
+            conditions.push(format!("created_at <= ${next_param}"));
+            next_param += 1;
+        }
+        if self.author.is_some() {
+            conditions.push(format!("author = ${next_param}"));
+        }
+        conditions
+    }
+}
+
+const DEFAULT_PAGE_SIZE: i64 = 3;
+const MAX_PAGE_SIZE: i64 = 100;
+const TOKEN_TTL: &str = "1 hour";
+
+#[derive(Deserialize)]
+struct ListQuery {
+    token: Option<String>,
+    limit: Option<i64>,
+    sort: Option<SortField>,
+    dir: Option<SortDir>,
+    /// RFC 3339 timestamps; parsed by hand so a bad one is a plain 400.
+    created_after: Option<String>,
+    created_before: Option<String>,
+    /// Cut each quote to this many characters; not carried by tokens.
+    preview: Option<usize>,
+}
+
+/// Parses an optional RFC 3339 timestamp, `Err` if it is present but invalid.
+fn day_19_parse_timestamp(
+    value: Option<&str>,
+) -> Result<Option<chrono::DateTime<chrono::Utc>>, ()> {
+    value
+        .map(|value| {
+            chrono::DateTime::parse_from_rfc3339(value)
+                .map(|timestamp| timestamp.with_timezone(&chrono::Utc))
+                .map_err(|_| ())
+        })
+        .transpose()
+}
+
+/// Consumes `token`, pruning expired tokens first.
+async fn day_19_take_token(pool: &PgPool, token: &str) -> sqlx::Result<Option<ListCursor>> {
+    sqlx::query(&format!(
+        "DELETE FROM pagination_tokens WHERE created_at < NOW() - INTERVAL '{TOKEN_TTL}'"
+    ))
+    .execute(pool)
+    .await?;
+    sqlx::query_as::<_, ListCursor>(
+        r#"DELETE FROM pagination_tokens WHERE token = $1
+        RETURNING "offset", "limit", sort, dir, last_id, last_created_at, last_author, last_version,
+        created_after, created_before, author"#,
+    )
+    .bind(token)
+    .fetch_optional(pool)
+    .await
+}
+
+async fn day_19_store_token(pool: &PgPool, token: &str, cursor: ListCursor) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"INSERT INTO pagination_tokens
+        (token, "offset", "limit", sort, dir, last_id, last_created_at, last_author, last_version,
+        created_after, created_before, author)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)"#,
+    )
+    .bind(token)
+    .bind(cursor.offset)
+    .bind(cursor.limit)
+    .bind(cursor.sort)
+    .bind(cursor.dir)
+    .bind(cursor.last_id)
+    .bind(cursor.last_created_at)
+    .bind(cursor.last_author)
+    .bind(cursor.last_version)
+    .bind(cursor.created_after)
+    .bind(cursor.created_before)
+    .bind(cursor.author)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn day_19_list(
+    State(state): State<Arc<Day19AppState>>,
+    Day19Query(params): Day19Query<ListQuery>,
+) -> Result<Response, Day19Error> {
+    day_19_list_page(&state, params, None).await
+}
+
+/// Like `/19/list`, but only through quotes by `author`. An author without
+/// quotes gets an empty page.
+async fn day_19_author_list(
+    State(state): State<Arc<Day19AppState>>,
+    Day19Path(author): Day19Path<String>,
+    Day19Query(params): Day19Query<ListQuery>,
+) -> Result<Response, Day19Error> {
+    day_19_list_page(&state, params, Some(author)).await
+}
+
+/// `WHERE` clause joining `conditions`, or nothing without any.
+fn day_19_where(conditions: &[String]) -> String {
+    if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    }
+}
+
+/// Serves one page of quotes, restricted to `author` if given.
+async fn day_19_list_page(
+    state: &Day19AppState,
+    params: ListQuery,
+    author: Option<String>,
+) -> Result<Response, Day19Error> {
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Ok(day_19_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_limit",
+            format!("limit must be between 1 and {MAX_PAGE_SIZE}"),
+        ));
+    }
+    if params
+        .preview
+        .is_some_and(|preview| !(1..=MAX_QUOTE_LEN).contains(&preview))
+    {
+        return Ok(day_19_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_preview",
+            format!("preview must be between 1 and {MAX_QUOTE_LEN}"),
+        ));
+    }
+    let (Ok(created_after), Ok(created_before)) = (
+        day_19_parse_timestamp(params.created_after.as_deref()),
+        day_19_parse_timestamp(params.created_before.as_deref()),
+    ) else {
+        return Ok(day_19_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_timestamp",
+            "created_after and created_before must be RFC 3339 timestamps",
+        ));
+    };
+    // a token carries the page size, ordering and filters it was minted with
+    let cursor = match params.token {
+        // a token only continues the listing it was minted for
+        Some(token) => match day_19_take_token(&state.pool, &token).await? {
+            Some(cursor) if cursor.author == author => cursor,
+            _ => {
+                return Ok(day_19_error(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_token",
+                    "unknown or expired token",
+                ));
+            }
+        },
+        None => ListCursor {
+            offset: 0,
+            limit,
+            sort: params.sort.unwrap_or_default(),
+            dir: params.dir.unwrap_or_default(),
+            last_id: None,
+            last_created_at: None,
+            last_author: None,
+            last_version: None,
+            created_after,
+            created_before,
+            author,
+        },
+    };
+
+    // `id` breaks ties in the same direction so `(column, id)` is a total order
+    let (dir, after) = (cursor.dir.keyword(), cursor.dir.after());
+    let column = cursor.sort.column();
+    let mut conditions = Vec::new();
+    if cursor.last_id.is_some() {
+        conditions.push(format!("({column}, id) {after} ($2, $3)"));
+    }
+    // placeholders continue after the limit and any keyset values
+    conditions.extend(cursor.filter_conditions(2 + 2 * conditions.len()));
+    let filter = day_19_where(&conditions);
+    let sql = format!("SELECT * FROM quotes {filter} ORDER BY {column} {dir}, id {dir} LIMIT $1");
+    // fetch one extra row to learn whether another page follows
+    let mut query = sqlx::query_as::<_, Quote>(&sql).bind(cursor.limit + 1);
+    if let Some(last_id) = cursor.last_id {
+        query = match cursor.sort {
+            SortField::CreatedAt => query.bind(cursor.last_created_at),
+            SortField::Author => query.bind(cursor.last_author.clone()),
+            SortField::Version => query.bind(cursor.last_version),
+        }
+        .bind(last_id);
+    }
+    if let Some(created_after) = cursor.created_after {
+        query = query.bind(created_after);
+    }
+    if let Some(created_before) = cursor.created_before {
+        query = query.bind(created_before);
+    }
+    if let Some(author) = &cursor.author {
+        query = query.bind(author);
+    }
+
+    // the total ignores the keyset so it stays the same on every page
+    let count_sql = format!(
+        "SELECT COUNT(*) FROM quotes {}",
+        day_19_where(&cursor.filter_conditions(1))
+    );
+    let mut count_query = sqlx::query_scalar::<_, i64>(&count_sql);
+    if let Some(created_after) = cursor.created_after {
+        count_query = count_query.bind(created_after);
+    }
+    if let Some(created_before) = cursor.created_before {
+        count_query = count_query.bind(created_before);
+    }
+    if let Some(author) = &cursor.author {
+        count_query = count_query.bind(author);
+    }
+    let total_quotes = count_query.fetch_one(&state.pool).await?;
+
+    Ok(match query.fetch_all(&state.pool).await {
+        Ok(mut quotes) => {
+            let has_more = quotes.len() as i64 > cursor.limit;
+            quotes.truncate(cursor.limit as usize);
+            let offset = cursor.offset + quotes.len() as i64;
+            let next_token = match quotes.last() {
+                Some(last) if has_more => {
+                    let next_token: String = (&mut *state.token_rng.lock().unwrap())
+                        .sample_iter(&Alphanumeric)
+                        .take(16)
+                        .map(char::from)
+                        .collect();
+                    let next = ListCursor {
+                        offset,
+                        last_id: Some(last.id),
+                        last_created_at: Some(last.created_at),
+                        last_author: Some(last.author.clone()),
+                        last_version: Some(last.version),
+                        ..cursor
+                    };
+                    day_19_store_token(&state.pool, &next_token, next).await?;
+                    Some(next_token)
+                }
+                _ => None,
+            };
+            let quotes_page = QuotePage {
+                quotes: quotes
+                    .into_iter()
+                    .map(|quote| ListedQuote::new(quote, params.preview))
+                    .collect(),
+                page: (offset + cursor.limit - 1) / cursor.limit,
+                next_token,
+                total_quotes,
+                total_pages: (total_quotes + cursor.limit - 1) / cursor.limit,
+            };
+            (
+                StatusCode::OK,
+                Body::from(serde_json::to_string(&quotes_page)?),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            warn!("list: error fetching quotes: {:?}", err);
+            day_19_internal("failed to fetch quotes")
+        }
+    })
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: Option<String>,
+}
+
+/// Escapes `LIKE` wildcards so user input only ever matches literally.
+fn escape_like(pattern: &str) -> String {
+    let mut escaped = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+async fn day_19_search(
+    State(state): State<Arc<Day19AppState>>,
+    Day19Query(query): Day19Query<SearchQuery>,
+) -> Result<Response, Day19Error> {
+    let Some(q) = query.q.filter(|q| !q.is_empty()) else {
+        return Ok(day_19_error(
+            StatusCode::BAD_REQUEST,
+            "missing_query",
+            "q must be a non-empty search term",
+        ));
+    };
+    let pattern = format!("%{}%", escape_like(&q));
+    let quotes = match sqlx::query_as::<_, Quote>(
+        r"SELECT * FROM quotes
+        WHERE (author ILIKE $1 ESCAPE '\' OR quote ILIKE $1 ESCAPE '\') AND deleted_at IS NULL
+        ORDER BY created_at ASC",
+    )
+    .bind(pattern)
+    .fetch_all(&state.pool)
+    .await
+    {
+        Ok(quotes) => quotes,
+        Err(err) => {
+            warn!("search: error fetching quotes: {:?}", err);
+            return Ok(day_19_internal("failed to search quotes"));
+        }
+    };
+    Ok((StatusCode::OK, Body::from(serde_json::to_string(&quotes)?)).into_response())
+}
+
+/// Rows fetched per round trip while streaming an export.
+const EXPORT_BATCH_SIZE: i64 = 500;
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    format: Option<String>,
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn csv_row(quote: &Quote) -> String {
+    format!(
+        "{},{},{},{},{}\n",
+        quote.id,
+        csv_field(&quote.author),
+        csv_field(&quote.quote),
+        quote.created_at.to_rfc3339(),
+        quote.version
+    )
+}
+
+async fn day_19_export(
+    State(state): State<Arc<Day19AppState>>,
+    Day19Query(query): Day19Query<ExportQuery>,
+) -> Result<Response, Day19Error> {
+    match query.format.as_deref() {
+        None | Some("csv") => {}
+        Some("json") => {
+            let quotes = sqlx::query_as::<_, Quote>(
+                "SELECT * FROM quotes WHERE deleted_at IS NULL ORDER BY created_at ASC, id ASC",
+            )
+            .fetch_all(&state.pool)
+            .await?;
+            return Ok(Json(quotes).into_response());
+        }
+        Some(_) => {
+            return Ok(day_19_error(
+                StatusCode::BAD_REQUEST,
+                "invalid_format",
+                "format must be csv or json",
+            ));
+        }
+    }
+
+    // page through the table by (created_at, id) so only one batch is held at a time
+    let header = stream::once(async { Ok("id,author,quote,created_at,version\n".to_string()) });
+    let rows = stream::try_unfold(
+        (state.pool.clone(), None, false),
+        |(pool, last, done)| async move {
+            if done {
+                return Ok::<_, sqlx::Error>(None);
+            }
+            let batch = match last {
+                None => {
+                    sqlx::query_as::<_, Quote>(
+                        "SELECT * FROM quotes WHERE deleted_at IS NULL
+                        ORDER BY created_at ASC, id ASC LIMIT $1",
+                    )
+                    .bind(EXPORT_BATCH_SIZE)
+                    .fetch_all(&pool)
+                    .await?
+                }
+                Some((created_at, id)) => sqlx::query_as::<_, Quote>(
+                    "SELECT * FROM quotes WHERE (created_at, id) > ($1, $2) AND deleted_at IS NULL
+                        ORDER BY created_at ASC, id ASC LIMIT $3",
+                )
+                .bind(created_at)
+                .bind(id)
+                .bind(EXPORT_BATCH_SIZE)
+                .fetch_all(&pool)
+                .await?,
+            };
+            let Some(last_quote) = batch.last() else {
+                return Ok(None);
+            };
+            let last = Some((last_quote.created_at, last_quote.id));
+            let done = (batch.len() as i64) < EXPORT_BATCH_SIZE;
+            let chunk = batch.iter().map(csv_row).collect::<String>();
+            Ok(Some((chunk, (pool, last, done))))
+        },
+    );
+    Ok((
+        [(CONTENT_TYPE, "text/csv")],
+        Body::from_stream(header.chain(rows)),
+    )
+        .into_response())
+}
+
+#[derive(Serialize)]
+struct QuoteCount {
+    count: i64,
+}
+
+async fn day_19_count(
+    State(state): State<Arc<Day19AppState>>,
+) -> Result<Json<QuoteCount>, Day19Error> {
+    let count = sqlx::query_scalar("SELECT COUNT(*) FROM quotes WHERE deleted_at IS NULL")
+        .fetch_one(&state.pool)
+        .await?;
+    Ok(Json(QuoteCount { count }))
+}
+
+#[derive(Serialize, FromRow)]
+struct AuthorCount {
+    author: String,
+    count: i64,
+}
+
+async fn day_19_authors(
+    State(state): State<Arc<Day19AppState>>,
+) -> Result<Json<Vec<AuthorCount>>, Day19Error> {
+    let authors = sqlx::query_as::<_, AuthorCount>(
+        "SELECT author, COUNT(*) AS count FROM quotes WHERE deleted_at IS NULL
+        GROUP BY author ORDER BY count DESC, author ASC",
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(authors))
+}
+
+#[derive(Deserialize)]
+struct RandomQuery {
+    author: Option<String>,
+}
+
+async fn day_19_random(
+    State(state): State<Arc<Day19AppState>>,
+    Day19Query(query): Day19Query<RandomQuery>,
+) -> Result<Response, Day19Error> {
+    let quote = sqlx::query_as::<_, Quote>(
+        "SELECT * FROM quotes WHERE ($1::TEXT IS NULL OR author = $1) AND deleted_at IS NULL
+        ORDER BY RANDOM() LIMIT 1",
+    )
+    .bind(query.author)
+    .fetch_optional(&state.pool)
+    .await?;
+    Ok(match quote {
+        Some(quote) => (StatusCode::OK, Body::from(serde_json::to_string(&quote)?)).into_response(),
+        None => day_19_not_found(),
+    })
+}
+
+const DEFAULT_RECENT_COUNT: i64 = 10;
+const MAX_RECENT_COUNT: i64 = 100;
+
+#[derive(Deserialize)]
+struct RecentQuery {
+    limit: Option<i64>,
+}
+
+/// The most recently edited quotes, newest edit first.
+async fn day_19_recent(
+    State(state): State<Arc<Day19AppState>>,
+    Day19Query(query): Day19Query<RecentQuery>,
+) -> Result<Response, Day19Error> {
+    let limit = query.limit.unwrap_or(DEFAULT_RECENT_COUNT);
+    if !(1..=MAX_RECENT_COUNT).contains(&limit) {
+        return Ok(day_19_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_limit",
+            format!("limit must be between 1 and {MAX_RECENT_COUNT}"),
+        ));
+    }
+    let quotes = sqlx::query_as::<_, Quote>(
+        "SELECT * FROM quotes WHERE version > 1 AND deleted_at IS NULL
+        ORDER BY updated_at DESC NULLS LAST, id ASC LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(&state.pool)
+    .await?;
+    Ok((StatusCode::OK, Body::from(serde_json::to_string(&quotes)?)).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::to_bytes;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn send(app: &Router, request: Request) -> (StatusCode, serde_json::Value) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    fn draft(forwarded_for: &str) -> Request {
+        Request::post("/19/draft")
+            .header(CONTENT_TYPE, "application/json")
+            .header("x-forwarded-for", forwarded_for)
+            .body(Body::from(r#"{"author":"Santa","quote":"Ho ho ho!"}"#))
+            .unwrap()
+    }
+
+    #[test]
+    fn client_ip_takes_the_hop_the_proxy_appended() {
+        let request = Request::get("/")
+            .header("x-forwarded-for", "10.0.0.1, 203.0.113.7")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(
+            client_ip(&request),
+            "203.0.113.7".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn client_ip_falls_back_to_the_peer() {
+        let mut request = Request::get("/").body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([198, 51, 100, 2], 4000))));
+        assert_eq!(
+            client_ip(&request),
+            "198.51.100.2".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn fourth_rapid_draft_is_rate_limited(pool: PgPool) {
+        let app = day_19_routes(Day19AppState {
+            write_limiter: WriteLimiter::new(WriteLimitConfig {
+                burst: 3,
+                interval: Duration::from_secs(60),
+            }),
+            ..Day19AppState::new(pool)
+        });
+        // a spoofed left-most entry must not buy a fresh bucket
+        for spoofed in ["10.0.0.1", "10.0.0.2", "10.0.0.3"] {
+            let (status, _) = send(&app, draft(&format!("{spoofed}, 203.0.113.7"))).await;
+            assert_eq!(status, StatusCode::CREATED);
+        }
+        let (status, body) = send(&app, draft("10.0.0.4, 203.0.113.7")).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(body["code"], "rate_limited");
+        let (status, _) = send(&app, draft("198.51.100.2")).await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    fn edit(id: &str, quote: &str) -> Request {
+        Request::put(format!("/19/undo/{id}"))
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                serde_json::json!({"author": "Santa", "quote": quote}).to_string(),
+            ))
+            .unwrap()
+    }
+
+    fn get(uri: String) -> Request {
+        Request::get(uri).body(Body::empty()).unwrap()
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn concurrent_edits_keep_every_revision(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        let (_, created) = send(&app, draft("203.0.113.7")).await;
+        let id = created["id"].as_str().unwrap();
+        let (first, second) = tokio::join!(
+            send(&app, edit(id, "first edit")),
+            send(&app, edit(id, "second edit")),
+        );
+        assert_eq!(first.0, StatusCode::OK);
+        assert_eq!(second.0, StatusCode::OK);
+        let mut versions = [first.1["version"].as_i64(), second.1["version"].as_i64()];
+        versions.sort();
+        assert_eq!(versions, [Some(2), Some(3)]);
+
+        let (status, v1) = send(&app, get(format!("/19/cite/{id}/version/1"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(v1["quote"], "Ho ho ho!");
+        let (status, v2) = send(&app, get(format!("/19/cite/{id}/version/2"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_ne!(v2["quote"], "Ho ho ho!");
+        let (_, current) = send(&app, get(format!("/19/cite/{id}"))).await;
+        assert_eq!(current["version"], 3);
+        assert_ne!(current["quote"], v2["quote"]);
+    }
+
+    /// Status and `code` of an error response, checking it is a JSON envelope.
+    async fn send_error(app: &Router, request: Request) -> (StatusCode, String) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(body["error"].is_string());
+        (status, body["code"].as_str().unwrap().to_string())
+    }
+
+    /// A pool whose database never answers.
+    fn unreachable_pool() -> PgPool {
+        sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://postgres@127.0.0.1:1/quotes")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn database_errors_use_the_envelope() {
+        let app = day_19_routes(Day19AppState::new(unreachable_pool()));
+        for uri in ["/19/count", "/19/authors", "/19/recent", "/19/list"] {
+            let request = Request::get(uri).body(Body::empty()).unwrap();
+            assert_eq!(
+                send_error(&app, request).await,
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal".to_string()),
+                "{uri}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rejected_extractors_use_the_envelope() {
+        let app = day_19_routes(Day19AppState::new(unreachable_pool()));
+        let request = Request::post("/19/import")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from("[{"))
+            .unwrap();
+        assert_eq!(
+            send_error(&app, request).await,
+            (StatusCode::BAD_REQUEST, "invalid_body".to_string())
+        );
+        let request = Request::post("/19/remove-batch")
+            .body(Body::from(r#"{"ids":[]}"#))
+            .unwrap();
+        assert_eq!(
+            send_error(&app, request).await,
+            (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "invalid_body".to_string()
+            )
+        );
+        let request = Request::get("/19/cite/not-a-uuid")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(
+            send_error(&app, request).await,
+            (StatusCode::BAD_REQUEST, "invalid_path".to_string())
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn unknown_quote_is_a_json_404(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        let request = get(format!("/19/cite/{}", uuid::Uuid::new_v4()));
+        assert_eq!(
+            send_error(&app, request).await,
+            (StatusCode::NOT_FOUND, "not_found".to_string())
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn five_quotes_at_three_per_page_are_two_pages(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        for _ in 0..5 {
+            send(&app, draft("203.0.113.7")).await;
+        }
+        let (status, first) = send(&app, get("/19/list?limit=3".to_string())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(first["total_quotes"], 5);
+        assert_eq!(first["total_pages"], 2);
+        assert_eq!(first["quotes"].as_array().unwrap().len(), 3);
+        let token = first["next_token"].as_str().unwrap();
+        let (_, second) = send(&app, get(format!("/19/list?token={token}"))).await;
+        assert_eq!(second["page"], 2);
+        assert_eq!(second["total_pages"], 2);
+        assert_eq!(second["quotes"].as_array().unwrap().len(), 2);
+        assert!(second["next_token"].is_null());
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn seeded_rng_mints_known_tokens(pool: PgPool) {
+        let app = day_19_routes(Day19AppState {
+            token_rng: Mutex::new(StdRng::seed_from_u64(2024)),
+            ..Day19AppState::new(pool)
+        });
+        for _ in 0..3 {
+            send(&app, draft("203.0.113.7")).await;
+        }
+        let (_, first) = send(&app, get("/19/list?limit=1".to_string())).await;
+        let token = first["next_token"].as_str().unwrap();
+        assert_eq!(token, "i0vgamjPBMKK7SkN");
+        let (_, second) = send(&app, get(format!("/19/list?token={token}"))).await;
+        assert_eq!(second["next_token"], "8Ti6Rkf1Hghz9BVQ");
+    }
+
+    /// Hands out `ids` in order, then random ones.
+    fn scripted_ids(ids: Vec<uuid::Uuid>) -> Box<dyn Fn() -> uuid::Uuid + Send + Sync> {
+        let ids = Mutex::new(ids.into_iter());
+        Box::new(move || {
+            ids.lock()
+                .unwrap()
+                .next()
+                .unwrap_or_else(uuid::Uuid::new_v4)
+        })
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn draft_id_collision_is_retried(pool: PgPool) {
+        let taken = uuid::Uuid::new_v4();
+        let fresh = uuid::Uuid::new_v4();
+        let app = day_19_routes(Day19AppState {
+            quote_ids: scripted_ids(vec![taken, taken, fresh]),
+            ..Day19AppState::new(pool)
+        });
+        let (status, first) = send(&app, draft("203.0.113.7")).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(first["id"], taken.to_string());
+        let (status, second) = send(&app, draft("203.0.113.7")).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(second["id"], fresh.to_string());
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn draft_gives_up_after_repeated_collisions(pool: PgPool) {
+        let taken = uuid::Uuid::new_v4();
+        let app = day_19_routes(Day19AppState {
+            quote_ids: scripted_ids(vec![taken; 1 + DRAFT_INSERT_ATTEMPTS]),
+            ..Day19AppState::new(pool)
+        });
+        send(&app, draft("203.0.113.7")).await;
+        let (status, body) = send(&app, draft("203.0.113.7")).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["code"], "internal");
+    }
+
+    fn quote_post(author: &str, quote: &str) -> QuotePost {
+        QuotePost {
+            author: author.to_string(),
+            quote: quote.to_string(),
+        }
+    }
+
+    #[test]
+    fn validate_checks_emptiness_and_length() {
+        assert_eq!(
+            quote_post(" \t", "Ho ho ho!").validate(),
+            Err("author must not be empty".to_string())
+        );
+        assert_eq!(
+            quote_post("Santa", &"o".repeat(MAX_QUOTE_LEN + 1)).validate(),
+            Err("quote must be at most 4096 characters".to_string())
+        );
+        // the limits count characters, not bytes
+        let author = "é".repeat(MAX_AUTHOR_LEN);
+        let quote = "ö".repeat(MAX_QUOTE_LEN);
+        assert_eq!(quote_post(&author, &quote).validate(), Ok(()));
+    }
+
+    /// A `/19/draft` request for a quote by `author`.
+    fn draft_quote(author: &str, quote: &str) -> Request {
+        Request::post("/19/draft")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                serde_json::json!({"author": author, "quote": quote}).to_string(),
+            ))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn invalid_quotes_are_rejected_before_the_database() {
+        let app = day_19_routes(Day19AppState::new(unreachable_pool()));
+        let (status, body) = send(&app, draft_quote("", "Ho ho ho!")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_quote");
+        assert_eq!(body["error"], "author must not be empty");
+        let long = "o".repeat(MAX_QUOTE_LEN + 1);
+        let (status, body) = send(&app, draft_quote("Santa", &long)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_quote");
+        let (status, body) = send(&app, edit(&uuid::Uuid::new_v4().to_string(), " ")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "quote must not be empty");
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn search_matches_literally_and_ignores_case(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        send(&app, draft_quote("Santa", "Ho ho ho!")).await;
+        send(&app, draft_quote("Rudolph", "100% shiny")).await;
+        send(&app, draft_quote("Dasher", "1000 shiny bells")).await;
+
+        let (status, found) = send(&app, get("/19/search?q=SANTA".to_string())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(found.as_array().unwrap().len(), 1);
+        assert_eq!(found[0]["author"], "Santa");
+        // `%` is a literal percent sign, not a wildcard
+        let (_, found) = send(&app, get("/19/search?q=0%25%20shiny".to_string())).await;
+        assert_eq!(found.as_array().unwrap().len(), 1);
+        assert_eq!(found[0]["author"], "Rudolph");
+        let (_, found) = send(&app, get("/19/search?q=_o".to_string())).await;
+        assert_eq!(found, serde_json::json!([]));
+
+        for uri in ["/19/search", "/19/search?q="] {
+            let (status, body) = send(&app, get(uri.to_string())).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+            assert_eq!(body["code"], "missing_query");
+        }
+    }
+
+    /// Authors of the quotes on a listing page, in order.
+    fn page_authors(page: &serde_json::Value) -> Vec<&str> {
+        page["quotes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|quote| quote["author"].as_str().unwrap())
+            .collect()
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn list_keeps_its_sort_across_pages(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        for author in ["Comet", "Blitzen", "Dancer", "Vixen", "Cupid"] {
+            send(&app, draft_quote(author, "Ho ho ho!")).await;
+        }
+        let (status, first) = send(&app, get("/19/list?sort=author&dir=desc&limit=2".into())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page_authors(&first), ["Vixen", "Dancer"]);
+        let token = first["next_token"].as_str().unwrap();
+        let (_, second) = send(&app, get(format!("/19/list?token={token}"))).await;
+        assert_eq!(page_authors(&second), ["Cupid", "Comet"]);
+        let token = second["next_token"].as_str().unwrap();
+        let (_, third) = send(&app, get(format!("/19/list?token={token}"))).await;
+        assert_eq!(page_authors(&third), ["Blitzen"]);
+
+        // without a sort the oldest quote comes first
+        let (_, default) = send(&app, get("/19/list?limit=2".into())).await;
+        assert_eq!(page_authors(&default), ["Comet", "Blitzen"]);
+        for uri in ["/19/list?sort=quote", "/19/list?dir=up"] {
+            let (status, body) = send(&app, get(uri.into())).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+            assert_eq!(body["code"], "invalid_query");
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn tokens_keep_the_page_size(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        for _ in 0..5 {
+            send(&app, draft("203.0.113.7")).await;
+        }
+        let (_, mut page) = send(&app, get("/19/list?limit=2".into())).await;
+        let mut sizes = vec![];
+        loop {
+            sizes.push((
+                page["page"].as_i64().unwrap(),
+                page["quotes"].as_array().unwrap().len(),
+            ));
+            assert_eq!(page["total_pages"], 3);
+            let Some(token) = page["next_token"].as_str() else {
+                break;
+            };
+            // a different limit alongside a token doesn't change the page size
+            page = send(&app, get(format!("/19/list?token={token}&limit=5")))
+                .await
+                .1;
+        }
+        assert_eq!(sizes, [(1, 2), (2, 2), (3, 1)]);
+
+        for limit in [0, MAX_PAGE_SIZE + 1] {
+            let (status, body) = send(&app, get(format!("/19/list?limit={limit}"))).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{limit}");
+            assert_eq!(body["code"], "invalid_limit");
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn tokens_survive_a_restart_until_they_expire(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool.clone()));
+        for _ in 0..3 {
+            send(&app, draft("203.0.113.7")).await;
+        }
+        let (_, first) = send(&app, get("/19/list?limit=1".into())).await;
+        let token = first["next_token"].as_str().unwrap();
+        assert_eq!(token.len(), 16);
+        assert!(token.chars().all(|c| c.is_ascii_alphanumeric()));
+
+        let restarted = day_19_routes(Day19AppState::new(pool.clone()));
+        let (status, second) = send(&restarted, get(format!("/19/list?token={token}"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(second["page"], 2);
+
+        let stale = second["next_token"].as_str().unwrap();
+        sqlx::query("UPDATE pagination_tokens SET created_at = NOW() - INTERVAL '2 hours'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let (status, body) = send(&restarted, get(format!("/19/list?token={stale}"))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_token");
+        let left: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pagination_tokens")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(left, 0);
+    }
+
+    fn import(quotes: serde_json::Value) -> Request {
+        Request::post("/19/import")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(quotes.to_string()))
+            .unwrap()
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn import_is_all_or_nothing(pool: PgPool) {
+        let id = uuid::Uuid::new_v4();
+        let app = day_19_routes(Day19AppState {
+            quote_ids: scripted_ids(vec![id, id]),
+            ..Day19AppState::new(pool)
+        });
+        let quotes = serde_json::json!([
+            {"author": "Santa", "quote": "Ho ho ho!"},
+            {"author": "Rudolph", "quote": "Follow the nose"},
+        ]);
+        // both rows get the same id, so the second insert fails
+        let (status, body) = send(&app, import(quotes.clone())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "import_failed");
+        assert_eq!(body["error"], "failed to import quote at index 1");
+        assert_eq!(send(&app, get("/19/count".into())).await.1["count"], 0);
+
+        let (status, body) = send(&app, import(quotes)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["count"], 2);
+        assert_eq!(body["ids"].as_array().unwrap().len(), 2);
+        assert_eq!(send(&app, get("/19/count".into())).await.1["count"], 2);
+
+        let invalid = serde_json::json!([
+            {"author": "Santa", "quote": "Ho ho ho!"},
+            {"author": "", "quote": "Follow the nose"},
+        ]);
+        let (status, body) = send(&app, import(invalid)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["error"],
+            "invalid quote at index 1: author must not be empty"
+        );
+        let too_many =
+            vec![serde_json::json!({"author": "Elf", "quote": "Hi"}); MAX_IMPORT_SIZE + 1];
+        let (status, body) = send(&app, import(too_many.into())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "too_many_quotes");
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("Ho ho ho"), "Ho ho ho");
+        assert_eq!(csv_field("Ho, ho"), "\"Ho, ho\"");
+        assert_eq!(csv_field("say \"ho\""), "\"say \"\"ho\"\"\"");
+        assert_eq!(csv_field("ho\nho"), "\"ho\nho\"");
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn export_as_csv_or_json(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        let (_, created) = send(&app, draft_quote("Santa, Claus", "Ho \"ho\"\nho")).await;
+        let id = created["id"].as_str().unwrap();
+        let (_, stored) = send(&app, get(format!("/19/cite/{id}"))).await;
+        let response = app.clone().oneshot(get("/19/export".into())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/csv");
+        let csv = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created_at = stored["created_at"].as_str().unwrap();
+        let created_at = chrono::DateTime::parse_from_rfc3339(created_at).unwrap();
+        assert_eq!(
+            String::from_utf8(csv.to_vec()).unwrap(),
+            format!(
+                "id,author,quote,created_at,version\n{id},\"Santa, Claus\",\"Ho \"\"ho\"\"\nho\",{},1\n",
+                created_at.to_rfc3339()
+            )
+        );
+
+        let (status, json) = send(&app, get("/19/export?format=json".into())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json, serde_json::json!([stored]));
+        let (status, body) = send(&app, get("/19/export?format=xml".into())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_format");
+    }
+
+    fn edit_if_match(id: &str, quote: &str, if_match: &str) -> Request {
+        let mut request = edit(id, quote);
+        request
+            .headers_mut()
+            .insert(IF_MATCH, if_match.parse().unwrap());
+        request
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn conflicting_edits_are_refused(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        let (_, created) = send(&app, draft("203.0.113.7")).await;
+        let id = created["id"].as_str().unwrap();
+
+        // both clients read version 1; only the first to write wins
+        let (status, first) = send(&app, edit_if_match(id, "first", "1")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(first["version"], 2);
+        let (status, current) = send(&app, edit_if_match(id, "second", "1")).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(current["quote"], "first");
+        assert_eq!(current["version"], 2);
+
+        let etag = format!("W/\"{id}-2\"");
+        let (status, _) = send(&app, edit_if_match(id, "third", &etag)).await;
+        assert_eq!(status, StatusCode::OK);
+        let mut stale = edit(id, "fourth");
+        *stale.uri_mut() = format!("/19/undo/{id}?version=2").parse().unwrap();
+        assert_eq!(send(&app, stale).await.0, StatusCode::CONFLICT);
+        let (status, body) = send(&app, edit_if_match(id, "fifth", "latest")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_version");
+        // without an expectation the last write still wins
+        let (status, last) = send(&app, edit(id, "sixth")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(last["version"], 4);
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn count_and_authors(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        let (status, count) = send(&app, get("/19/count".into())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(count, serde_json::json!({"count": 0}));
+        let (status, authors) = send(&app, get("/19/authors".into())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(authors, serde_json::json!([]));
+
+        for author in [
+            "Rudolph",
+            "Santa",
+            "Rudolph",
+            "Mrs Claus",
+            "Santa",
+            "Rudolph",
+        ] {
+            send(&app, draft_quote(author, "Ho ho ho!")).await;
+        }
+        let (_, count) = send(&app, get("/19/count".into())).await;
+        assert_eq!(count["count"], 6);
+        let (_, authors) = send(&app, get("/19/authors".into())).await;
+        assert_eq!(
+            authors,
+            serde_json::json!([
+                {"author": "Rudolph", "count": 3},
+                {"author": "Santa", "count": 2},
+                {"author": "Mrs Claus", "count": 1},
+            ])
+        );
+    }
+
+    /// Cites `id`, sending `etag` as `If-None-Match`; returns the status, the
+    /// ETag and the raw body.
+    async fn cite(app: &Router, id: &str, etag: Option<&str>) -> (StatusCode, String, Vec<u8>) {
+        let mut request = Request::get(format!("/19/cite/{id}"));
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        let response = app
+            .clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let etag = response.headers()[ETAG].to_str().unwrap().to_string();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, etag, body.to_vec())
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn cite_answers_304_for_an_unchanged_quote(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        let (_, created) = send(&app, draft("203.0.113.7")).await;
+        let id = created["id"].as_str().unwrap();
+
+        let (status, etag, body) = cite(&app, id, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(etag, format!("W/\"{id}-1\""));
+        assert!(!body.is_empty());
+        let (status, _, body) = cite(&app, id, Some(&etag)).await;
+        assert_eq!(status, StatusCode::NOT_MODIFIED);
+        assert!(body.is_empty());
+
+        send(&app, edit(id, "Merry Christmas!")).await;
+        let (status, changed, _) = cite(&app, id, Some(&etag)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(changed, format!("W/\"{id}-2\""));
+    }
+
+    fn page_ids(page: &serde_json::Value) -> Vec<String> {
+        page["quotes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|quote| quote["id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn pages_stay_stable_under_concurrent_changes(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        let mut drafted = vec![];
+        for _ in 0..4 {
+            let (_, created) = send(&app, draft("203.0.113.7")).await;
+            drafted.push(created["id"].as_str().unwrap().to_string());
+        }
+        let (_, first) = send(&app, get("/19/list?limit=2".into())).await;
+        let mut seen = page_ids(&first);
+        assert_eq!(seen, drafted[..2]);
+
+        // with an offset, removing a served quote would skip the third one
+        let remove = Request::delete(format!("/19/remove/{}", drafted[0]))
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(send(&app, remove).await.0, StatusCode::OK);
+        let (_, created) = send(&app, draft("203.0.113.7")).await;
+        drafted.push(created["id"].as_str().unwrap().to_string());
+
+        let mut token = first["next_token"].as_str().map(str::to_string);
+        while let Some(current) = token {
+            let (_, page) = send(&app, get(format!("/19/list?token={current}&limit=2"))).await;
+            seen.extend(page_ids(&page));
+            token = page["next_token"].as_str().map(str::to_string);
+        }
+        assert_eq!(seen, drafted);
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn random_picks_one_of_the_quotes(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        let (status, body) = send(&app, get("/19/random".into())).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "not_found");
+
+        let (_, santa) = send(&app, draft_quote("Santa", "Ho ho ho!")).await;
+        let (_, rudolph) = send(&app, draft_quote("Rudolph", "Follow the nose")).await;
+        let ids = [&santa["id"], &rudolph["id"]];
+        for _ in 0..5 {
+            let (status, picked) = send(&app, get("/19/random".into())).await;
+            assert_eq!(status, StatusCode::OK);
+            assert!(ids.contains(&&picked["id"]), "{picked}");
+        }
+        let (_, picked) = send(&app, get("/19/random?author=Rudolph".into())).await;
+        assert_eq!(picked["id"], rudolph["id"]);
+        let (status, _) = send(&app, get("/19/random?author=Grinch".into())).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    fn draft_with_key(key: &str, quote: &str) -> Request {
+        let mut request = draft_quote("Santa", quote);
+        request
+            .headers_mut()
+            .insert("idempotency-key", key.parse().unwrap());
+        request
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn idempotency_key_drafts_once(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        let (status, first) = send(&app, draft_with_key("sleigh-1", "Ho ho ho!")).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, retried) = send(&app, draft_with_key("sleigh-1", "Ho ho ho!")).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(retried["id"], first["id"]);
+        assert_eq!(send(&app, get("/19/count".into())).await.1["count"], 1);
+
+        let (status, body) = send(&app, draft_with_key("sleigh-1", "Merry Christmas!")).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], "idempotency_key_reused");
+        let (status, other) = send(&app, draft_with_key("sleigh-2", "Ho ho ho!")).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_ne!(other["id"], first["id"]);
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn list_filters_to_a_time_window(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool.clone()));
+        for (author, created_at) in [
+            ("Eve", "2024-12-24T23:59:59Z"),
+            ("Morning", "2024-12-25T08:00:00Z"),
+            ("Evening", "2024-12-25T20:00:00Z"),
+            ("Boxing", "2024-12-26T00:00:01Z"),
+        ] {
+            let (_, created) = send(&app, draft_quote(author, "Ho ho ho!")).await;
+            let id = uuid::Uuid::parse_str(created["id"].as_str().unwrap()).unwrap();
+            sqlx::query("UPDATE quotes SET created_at = $1::TEXT::TIMESTAMPTZ WHERE id = $2")
+                .bind(created_at)
+                .bind(id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        let window = "created_after=2024-12-25T00:00:00Z&created_before=2024-12-26T00:00:00Z";
+        let (status, first) = send(&app, get(format!("/19/list?{window}&limit=1"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(first["total_quotes"], 2);
+        assert_eq!(page_authors(&first), ["Morning"]);
+        let token = first["next_token"].as_str().unwrap();
+        let (_, second) = send(&app, get(format!("/19/list?token={token}"))).await;
+        assert_eq!(page_authors(&second), ["Evening"]);
+        assert!(second["next_token"].is_null());
+
+        let (status, body) = send(&app, get("/19/list?created_after=yesterday".into())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_timestamp");
+    }
+
+    fn remove_batch(ids: serde_json::Value) -> Request {
+        Request::post("/19/remove-batch")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::json!({ "ids": ids }).to_string()))
+            .unwrap()
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn remove_batch_reports_missing_ids(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        let (_, first) = send(&app, draft("203.0.113.7")).await;
+        let (_, second) = send(&app, draft("203.0.113.7")).await;
+        let (_, kept) = send(&app, draft("203.0.113.7")).await;
+        let absent = uuid::Uuid::new_v4().to_string();
+        let ids = serde_json::json!([first["id"], absent, second["id"]]);
+        let (status, result) = send(&app, remove_batch(ids)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            result,
+            serde_json::json!({"removed": 2, "not_found": [absent]})
+        );
+        let (_, listed) = send(&app, get("/19/list".into())).await;
+        assert_eq!(page_ids(&listed), [kept["id"].as_str().unwrap()]);
+
+        let (status, body) = send(&app, remove_batch(serde_json::json!(["sleigh"]))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_id");
+        let too_many = vec![absent; MAX_REMOVE_BATCH_SIZE + 1];
+        let (status, body) = send(&app, remove_batch(too_many.into())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "too_many_ids");
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn author_list_pages_through_one_author(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        for n in 1..=4 {
+            send(&app, draft_quote("Mrs Claus", &format!("Cookie #{n}"))).await;
+            send(&app, draft_quote("Santa", "Ho ho ho!")).await;
+        }
+        let (status, first) = send(&app, get("/19/author/Mrs%20Claus/list?limit=3".into())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(first["total_quotes"], 4);
+        assert_eq!(page_authors(&first), ["Mrs Claus"; 3]);
+        let token = first["next_token"].as_str().unwrap();
+        let (_, second) = send(
+            &app,
+            get(format!("/19/author/Mrs%20Claus/list?token={token}")),
+        )
+        .await;
+        assert_eq!(second["quotes"][0]["quote"], "Cookie #4");
+        assert_eq!(second["page"], 2);
+        assert!(second["next_token"].is_null());
+
+        // an author's token doesn't continue another listing
+        let (_, first) = send(&app, get("/19/author/Mrs%20Claus/list?limit=3".into())).await;
+        let token = first["next_token"].as_str().unwrap();
+        let (status, body) = send(&app, get(format!("/19/list?token={token}"))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_token");
+
+        let (status, empty) = send(&app, get("/19/author/Grinch/list".into())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(empty["quotes"], serde_json::json!([]));
+        assert_eq!(empty["total_quotes"], 0);
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn preview_truncates_long_quotes(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        let long = "Twas the night before Christmas, when all through the house";
+        let (_, created) = send(&app, draft_quote("Clement", long)).await;
+        send(&app, draft_quote("Santa", "Ho ho ho!")).await;
+
+        let (status, page) = send(&app, get("/19/list?preview=10".into())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page["quotes"][0]["quote"], "Twas the n…");
+        assert_eq!(page["quotes"][0]["truncated"], true);
+        assert_eq!(page["quotes"][1]["quote"], "Ho ho ho!");
+        assert_eq!(page["quotes"][1]["truncated"], false);
+
+        let (_, full) = send(&app, get("/19/list".into())).await;
+        assert_eq!(full["quotes"][0]["quote"], long);
+        assert!(full["quotes"][0].get("truncated").is_none());
+        let (_, cited) = send(
+            &app,
+            get(format!("/19/cite/{}", created["id"].as_str().unwrap())),
+        )
+        .await;
+        assert_eq!(cited["quote"], long);
+
+        for preview in [0, MAX_QUOTE_LEN + 1] {
+            let (status, body) = send(&app, get(format!("/19/list?preview={preview}"))).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{preview}");
+            assert_eq!(body["code"], "invalid_preview");
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn recent_lists_edited_quotes(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        let (_, first) = send(&app, draft_quote("Santa", "Ho ho ho!")).await;
+        let (_, second) = send(&app, draft_quote("Rudolph", "Follow the nose")).await;
+        send(&app, draft_quote("Dasher", "On Dasher!")).await;
+        let (_, recent) = send(&app, get("/19/recent".into())).await;
+        assert_eq!(recent, serde_json::json!([]));
+
+        send(
+            &app,
+            edit(second["id"].as_str().unwrap(), "Follow the red nose"),
+        )
+        .await;
+        send(
+            &app,
+            edit(first["id"].as_str().unwrap(), "Merry Christmas!"),
+        )
+        .await;
+        let (status, recent) = send(&app, get("/19/recent".into())).await;
+        assert_eq!(status, StatusCode::OK);
+        let ids: Vec<_> = recent
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|quote| &quote["id"])
+            .collect();
+        assert_eq!(ids, [&first["id"], &second["id"]]);
+        let (_, recent) = send(&app, get("/19/recent?limit=1".into())).await;
+        assert_eq!(recent[0]["quote"], "Merry Christmas!");
+        let (status, _) = send(
+            &app,
+            get(format!("/19/recent?limit={}", MAX_RECENT_COUNT + 1)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn draft_points_at_the_new_quote(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        let response = app.clone().oneshot(draft("203.0.113.7")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let location = response.headers()[LOCATION].to_str().unwrap().to_string();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            location,
+            format!("/19/cite/{}", created["id"].as_str().unwrap())
+        );
+        let (status, cited) = send(&app, get(location)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(cited["id"], created["id"]);
+    }
+
+    #[tokio::test]
+    async fn malformed_quote_bodies_name_the_field() {
+        let app = day_19_routes(Day19AppState::new(unreachable_pool()));
+        let id = uuid::Uuid::new_v4();
+        for (body, error) in [
+            (r#"{"author": "Santa"}"#, "missing field: quote"),
+            (
+                r#"{"author": null, "quote": "Ho"}"#,
+                "missing field: author",
+            ),
+            (
+                r#"{"author": "Santa", "quote": 3}"#,
+                "field quote must be a string",
+            ),
+            (r#"["Santa", "Ho"]"#, "body must be a JSON object"),
+        ] {
+            for (method, uri) in [
+                ("POST", "/19/draft".to_string()),
+                ("PUT", format!("/19/undo/{id}")),
+            ] {
+                let request = Request::builder()
+                    .method(method)
+                    .uri(&uri)
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(Body::from(body))
+                    .unwrap();
+                let (status, response) = send(&app, request).await;
+                assert_eq!(status, StatusCode::BAD_REQUEST, "{uri} {body}");
+                assert_eq!(response["code"], "invalid_body");
+                assert_eq!(response["error"], error, "{uri} {body}");
+            }
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs DATABASE_URL pointing at Postgres"]
+    async fn removed_quotes_can_be_restored_until_purged(pool: PgPool) {
+        let app = day_19_routes(Day19AppState::new(pool));
+        let (_, created) = send(&app, draft("203.0.113.7")).await;
+        let id = created["id"].as_str().unwrap();
+        let request = |method: &str, uri: String| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let (status, _) = send(&app, request("DELETE", format!("/19/remove/{id}"))).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, listed) = send(&app, get("/19/list".into())).await;
+        assert_eq!(listed["quotes"], serde_json::json!([]));
+        assert_eq!(
+            send(&app, get(format!("/19/cite/{id}"))).await.0,
+            StatusCode::NOT_FOUND
+        );
+
+        let (status, restored) = send(&app, request("POST", format!("/19/restore/{id}"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(restored["id"], id);
+        let (_, listed) = send(&app, get("/19/list".into())).await;
+        assert_eq!(page_ids(&listed), [id]);
+        // only trashed quotes can be restored
+        let (status, _) = send(&app, request("POST", format!("/19/restore/{id}"))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        send(&app, request("DELETE", format!("/19/remove/{id}"))).await;
+        let (_, purged) = send(&app, request("POST", "/19/purge".into())).await;
+        assert_eq!(purged, serde_json::json!({"purged": 1}));
+        let (status, _) = send(&app, request("POST", format!("/19/restore/{id}"))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    fn authorized(mut request: Request, authorization: Option<&str>) -> Request {
+        if let Some(authorization) = authorization {
+            request
+                .headers_mut()
+                .insert(AUTHORIZATION, authorization.parse().unwrap());
+        }
+        request
+    }
+
+    #[tokio::test]
+    async fn writes_need_the_bearer_token() {
+        let app = day_19_routes(Day19AppState {
+            api_token: Some("north-pole".to_string()),
+            ..Day19AppState::new(unreachable_pool())
+        });
+        let reset = || Request::post("/19/reset").body(Body::empty()).unwrap();
+        for authorization in [None, Some("Bearer south-pole"), Some("north-pole")] {
+            for request in [draft("203.0.113.7"), reset()] {
+                let request = authorized(request, authorization);
+                let uri = request.uri().clone();
+                let response = app.clone().oneshot(request).await.unwrap();
+                assert_eq!(
+                    response.status(),
+                    StatusCode::UNAUTHORIZED,
+                    "{uri} {authorization:?}"
+                );
+                assert_eq!(response.headers()[WWW_AUTHENTICATE], "Bearer");
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                assert_eq!(body["code"], "unauthorized");
+            }
+        }
+
+        // past the gate, a bad quote is refused before the database is asked
+        let bearer = Some("Bearer north-pole");
+        let (status, body) = send(&app, authorized(draft_quote("", "Ho ho ho!"), bearer)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_quote");
+        // and a reset reaches the (unreachable) database
+        let (status, body) = send(&app, authorized(reset(), bearer)).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["code"], "internal");
+    }
+
+    #[tokio::test]
+    async fn reads_are_not_gated() {
+        let app = day_19_routes(Day19AppState {
+            api_token: Some("north-pole".to_string()),
+            ..Day19AppState::new(unreachable_pool())
+        });
+        // reaching the unreachable database means the gate let them through
+        for uri in ["/19/list", "/19/count"] {
+            let (status, body) = send(&app, get(uri.into())).await;
+            assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR, "{uri}");
+            assert_eq!(body["code"], "internal");
+        }
+    }
+}
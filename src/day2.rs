@@ -0,0 +1,433 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use axum::{
+    Json, Router,
+    extract::Query,
+    http::{HeaderMap, StatusCode, header::ACCEPT},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use serde::Deserialize;
+use serde_json::json;
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/2/dest", get(day_2_dest))
+        .route("/2/key", get(day_2_key))
+        .route("/2/cidr", get(day_2_cidr))
+        .route("/2/v6/dest", get(day_2_v6_dest))
+        .route("/2/v6/key", get(day_2_v6_key))
+        .route("/2/v6/batch", post(day_2_v6_batch))
+        .route("/2/roundtrip", get(day_2_roundtrip))
+}
+
+/// One round of the IPv4 cipher: adds `key` to `from` octet by octet,
+/// wrapping each octet on overflow.
+fn ipv4_dest(from: Ipv4Addr, key: Ipv4Addr) -> Ipv4Addr {
+    let mut octets = from.octets();
+    octets
+        .iter_mut()
+        .zip(key.octets())
+        .for_each(|(from, key)| *from = from.wrapping_add(key));
+    Ipv4Addr::from(octets)
+}
+
+/// Recovers the key of a single `ipv4_dest` round, octet by octet.
+fn ipv4_key(from: Ipv4Addr, to: Ipv4Addr) -> Ipv4Addr {
+    let mut octets = to.octets();
+    octets
+        .iter_mut()
+        .zip(from.octets())
+        .for_each(|(to, from)| *to = to.wrapping_sub(from));
+    Ipv4Addr::from(octets)
+}
+
+/// The IPv6 cipher XORs the address with the key.
+fn ipv6_dest(from: Ipv6Addr, key: Ipv6Addr) -> Ipv6Addr {
+    Ipv6Addr::from_bits(from.to_bits() ^ key.to_bits())
+}
+
+/// XOR is its own inverse, so the key is the XOR of both addresses.
+fn ipv6_key(from: Ipv6Addr, to: Ipv6Addr) -> Ipv6Addr {
+    Ipv6Addr::from_bits(from.to_bits() ^ to.to_bits())
+}
+
+const DAY_2_MAX_ROUNDS: u32 = 1000;
+
+fn day_2_rounds(rounds: Option<u32>) -> Result<u32, StatusCode> {
+    match rounds.unwrap_or(1) {
+        rounds @ 1..=DAY_2_MAX_ROUNDS => Ok(rounds),
+        _ => Err(StatusCode::BAD_REQUEST),
+    }
+}
+
+/// Whether `Accept` lists `application/json`.
+fn day_2_accepts_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| {
+            accept.split(',').any(|media_type| {
+                media_type.split(';').next().map(str::trim) == Some("application/json")
+            })
+        })
+}
+
+/// Answers with the bare address, or `{field: address}` for JSON clients.
+fn day_2_address_response(headers: &HeaderMap, field: &str, address: String) -> Response {
+    if day_2_accepts_json(headers) {
+        Json(json!({ field: address })).into_response()
+    } else {
+        address.into_response()
+    }
+}
+
+#[derive(Deserialize)]
+struct Day2DestQuery {
+    from: Ipv4Addr,
+    key: Ipv4Addr,
+    rounds: Option<u32>,
+}
+
+async fn day_2_dest(
+    headers: HeaderMap,
+    query: Query<Day2DestQuery>,
+) -> Result<Response, StatusCode> {
+    let rounds = day_2_rounds(query.rounds)?;
+    let mut dest = query.from;
+    for _ in 0..rounds {
+        dest = ipv4_dest(dest, query.key);
+    }
+
+    Ok(day_2_address_response(&headers, "dest", dest.to_string()))
+}
+
+/// Widest block `/2/cidr` transforms, which bounds the response to 64Ki lines.
+const DAY_2_MIN_CIDR_PREFIX: u32 = 16;
+
+#[derive(Deserialize)]
+struct Day2CidrQuery {
+    net: String,
+    key: Ipv4Addr,
+}
+
+/// Parses `a.b.c.d/prefix`, returning the network address and prefix length.
+/// Host bits set in the address are ignored.
+fn day_2_parse_cidr(net: &str) -> Option<(Ipv4Addr, u32)> {
+    let (addr, prefix) = net.split_once('/')?;
+    let addr = addr.parse::<Ipv4Addr>().ok()?;
+    let prefix = prefix.parse::<u32>().ok().filter(|&prefix| prefix <= 32)?;
+    let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+    Some((Ipv4Addr::from_bits(addr.to_bits() & mask), prefix))
+}
+
+async fn day_2_cidr(query: Query<Day2CidrQuery>) -> Result<String, StatusCode> {
+    let (network, prefix) = day_2_parse_cidr(&query.net).ok_or(StatusCode::BAD_REQUEST)?;
+    if prefix < DAY_2_MIN_CIDR_PREFIX {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let first = network.to_bits();
+    let last = first | (u32::MAX >> prefix);
+    // the network and broadcast addresses aren't hosts, except in /31 and /32
+    let hosts = if prefix >= 31 {
+        first..=last
+    } else {
+        first + 1..=last - 1
+    };
+
+    Ok(hosts
+        .map(|host| ipv4_dest(Ipv4Addr::from_bits(host), query.key).to_string())
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+#[derive(Deserialize)]
+struct Day2KeyQuery {
+    from: Ipv4Addr,
+    to: Ipv4Addr,
+    rounds: Option<u32>,
+}
+
+async fn day_2_key(headers: HeaderMap, query: Query<Day2KeyQuery>) -> Result<Response, StatusCode> {
+    let rounds = day_2_rounds(query.rounds)?;
+    let diff = ipv4_key(query.from, query.to).octets();
+
+    // each octet of the key was added `rounds` times, so solve
+    // `key * rounds == to - from (mod 256)`; even round counts may have no solution
+    let mut key = [0u8; 4];
+    for (key, diff) in key.iter_mut().zip(diff) {
+        *key = (0..=u8::MAX)
+            .find(|candidate| candidate.wrapping_mul(rounds as u8) == diff)
+            .ok_or(StatusCode::BAD_REQUEST)?;
+    }
+
+    Ok(day_2_address_response(
+        &headers,
+        "key",
+        Ipv4Addr::from(key).to_string(),
+    ))
+}
+
+#[derive(Deserialize)]
+struct Day2V6DestQuery {
+    from: Ipv6Addr,
+    key: Ipv6Addr,
+}
+
+async fn day_2_v6_dest(headers: HeaderMap, query: Query<Day2V6DestQuery>) -> Response {
+    let dest = ipv6_dest(query.from, query.key);
+
+    day_2_address_response(&headers, "dest", dest.to_string())
+}
+
+#[derive(Deserialize)]
+struct Day2V6KeyQuery {
+    from: Ipv6Addr,
+    to: Ipv6Addr,
+}
+
+async fn day_2_v6_key(headers: HeaderMap, query: Query<Day2V6KeyQuery>) -> Response {
+    let key = ipv6_key(query.from, query.to);
+
+    day_2_address_response(&headers, "key", key.to_string())
+}
+
+#[derive(Deserialize)]
+struct Day2V6BatchEntry {
+    from: String,
+    key: String,
+}
+
+async fn day_2_v6_batch(
+    Json(entries): Json<Vec<Day2V6BatchEntry>>,
+) -> Result<Json<Vec<String>>, (StatusCode, String)> {
+    entries
+        .iter()
+        .enumerate()
+        .map(
+            |(index, entry)| match (entry.from.parse(), entry.key.parse()) {
+                (Ok(from), Ok(key)) => Ok(ipv6_dest(from, key).to_string()),
+                _ => Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("invalid address at index {index}"),
+                )),
+            },
+        )
+        .collect::<Result<Vec<_>, _>>()
+        .map(Json)
+}
+
+#[derive(Deserialize)]
+struct Day2RoundtripQuery {
+    from: IpAddr,
+    key: IpAddr,
+}
+
+/// Encrypts `from` with `key`, then recovers the key from both addresses to
+/// check the cipher inverts. The address family picks the v4 or v6 cipher.
+async fn day_2_roundtrip(
+    query: Query<Day2RoundtripQuery>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let (dest, recovered_key) = match (query.from, query.key) {
+        (IpAddr::V4(from), IpAddr::V4(key)) => {
+            let dest = ipv4_dest(from, key);
+            (IpAddr::V4(dest), IpAddr::V4(ipv4_key(from, dest)))
+        }
+        (IpAddr::V6(from), IpAddr::V6(key)) => {
+            let dest = ipv6_dest(from, key);
+            (IpAddr::V6(dest), IpAddr::V6(ipv6_key(from, dest)))
+        }
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+    Ok(Json(json!({
+        "consistent": recovered_key == query.key,
+        "dest": dest.to_string(),
+        "recovered_key": recovered_key.to_string(),
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, body::to_bytes, extract::Request, http::header::CONTENT_TYPE};
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn send(request: Request) -> (StatusCode, String) {
+        let response = router().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn batch(body: &str) -> Request {
+        Request::post("/2/v6/batch")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[test]
+    fn ipv4_key_inverts_ipv4_dest() {
+        let from = Ipv4Addr::new(10, 0, 0, 1);
+        for key in [
+            Ipv4Addr::new(1, 2, 3, 255),
+            Ipv4Addr::new(255, 255, 255, 255),
+            Ipv4Addr::UNSPECIFIED,
+        ] {
+            assert_eq!(ipv4_key(from, ipv4_dest(from, key)), key);
+        }
+    }
+
+    #[test]
+    fn ipv4_dest_wraps_each_octet() {
+        assert_eq!(
+            ipv4_dest(
+                Ipv4Addr::new(128, 128, 33, 255),
+                Ipv4Addr::new(255, 0, 255, 1)
+            ),
+            Ipv4Addr::new(127, 128, 32, 0)
+        );
+    }
+
+    #[test]
+    fn ipv6_xor_is_its_own_inverse() {
+        let from: Ipv6Addr = "fe80::1".parse().unwrap();
+        let key: Ipv6Addr = "5:6:7::3333".parse().unwrap();
+        let dest = ipv6_dest(from, key);
+        assert_eq!(dest, "fe85:6:7::3332".parse::<Ipv6Addr>().unwrap());
+        assert_eq!(ipv6_dest(dest, key), from);
+        assert_eq!(ipv6_key(from, dest), key);
+    }
+
+    #[tokio::test]
+    async fn batch_transforms_every_entry() {
+        let body = r#"[{"from":"fe80::1","key":"5:6:7::3333"},{"from":"::","key":"::1"}]"#;
+        assert_eq!(
+            send(batch(body)).await,
+            (StatusCode::OK, r#"["fe85:6:7::3332","::1"]"#.to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn batch_names_the_invalid_entry() {
+        let body = r#"[
+            {"from":"fe80::1","key":"5:6:7::3333"},
+            {"from":"not an address","key":"::1"},
+            {"from":"::","key":"::1"}
+        ]"#;
+        assert_eq!(
+            send(batch(body)).await,
+            (
+                StatusCode::BAD_REQUEST,
+                "invalid address at index 1".to_string()
+            )
+        );
+    }
+
+    fn get(uri: &str) -> Request {
+        Request::get(uri).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn rounds_repeat_the_addition() {
+        assert_eq!(
+            send(get("/2/dest?from=10.0.0.1&key=1.2.3.100&rounds=3")).await,
+            (StatusCode::OK, "13.6.9.45".to_string())
+        );
+        assert_eq!(
+            send(get("/2/key?from=10.0.0.1&to=13.6.9.45&rounds=3")).await,
+            (StatusCode::OK, "1.2.3.100".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn rounds_out_of_range_are_rejected() {
+        for rounds in ["0", "1001"] {
+            let (status, _) = send(get(&format!(
+                "/2/dest?from=10.0.0.1&key=1.2.3.4&rounds={rounds}"
+            )))
+            .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "rounds={rounds}");
+        }
+        let (status, _) = send(get("/2/dest?from=10.0.0.1&key=1.2.3.4&rounds=1000")).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn cidr_transforms_the_hosts_of_a_block() {
+        assert_eq!(
+            send(get("/2/cidr?net=10.0.0.0/30&key=1.2.3.4")).await,
+            (StatusCode::OK, "11.2.3.5\n11.2.3.6".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn cidr_rejects_wide_or_malformed_blocks() {
+        for net in ["10.0.0.0/15", "10.0.0.0", "10.0.0.0/33", "10.0.0/24"] {
+            let (status, _) = send(get(&format!("/2/cidr?net={net}&key=1.2.3.4"))).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{net}");
+        }
+    }
+
+    fn accepting_json(uri: &str) -> Request {
+        Request::get(uri)
+            .header(ACCEPT, "text/html, application/json;q=0.9")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn v4_dest_is_plain_or_json() {
+        let uri = "/2/dest?from=10.0.0.0&key=1.2.3.255";
+        assert_eq!(
+            send(get(uri)).await,
+            (StatusCode::OK, "11.2.3.255".to_string())
+        );
+        assert_eq!(
+            send(accepting_json(uri)).await,
+            (StatusCode::OK, r#"{"dest":"11.2.3.255"}"#.to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn v6_key_is_plain_or_json() {
+        let uri = "/2/v6/key?from=aaaa::aaaa&to=5555:ffff:c:0:0:c1a9:ffff:c";
+        let key = "ffff:ffff:c::c1a9:ffff:aaa6";
+        assert_eq!(send(get(uri)).await, (StatusCode::OK, key.to_string()));
+        assert_eq!(
+            send(accepting_json(uri)).await,
+            (StatusCode::OK, format!(r#"{{"key":"{key}"}}"#))
+        );
+    }
+
+    #[tokio::test]
+    async fn roundtrip_is_consistent_for_random_inputs() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..50 {
+            let (from, key) = if rng.r#gen() {
+                (
+                    IpAddr::V4(Ipv4Addr::from_bits(rng.r#gen())),
+                    IpAddr::V4(Ipv4Addr::from_bits(rng.r#gen())),
+                )
+            } else {
+                (
+                    IpAddr::V6(Ipv6Addr::from_bits(rng.r#gen())),
+                    IpAddr::V6(Ipv6Addr::from_bits(rng.r#gen())),
+                )
+            };
+            let (status, body) = send(get(&format!("/2/roundtrip?from={from}&key={key}"))).await;
+            assert_eq!(status, StatusCode::OK);
+            let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+            assert_eq!(body["consistent"], true, "from={from} key={key}");
+            assert_eq!(body["recovered_key"], key.to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn roundtrip_rejects_mixed_families() {
+        let (status, _) = send(get("/2/roundtrip?from=10.0.0.1&key=::1")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
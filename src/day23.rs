@@ -0,0 +1,714 @@
+use std::{fmt, str::FromStr};
+
+use axum::{
+    Json, Router,
+    extract::{DefaultBodyLimit, Multipart, Path, Query, multipart::MultipartError},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+use tower_http::limit::RequestBodyLimitLayer;
+use tracing::*;
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/23/star", get(day_23_star))
+        .route("/23/present/{color}", get(day_23_present))
+        .route("/23/present/{color}/chain", get(day_23_present_chain))
+        .route("/23/ornament/{state}/{n}", get(day_23_ornament))
+        .merge(
+            Router::new()
+                .route("/23/lockfile", post(day_23_lockfile))
+                .route("/23/lockfile/validate", post(day_23_lockfile_validate))
+                .layer(DefaultBodyLimit::disable())
+                .layer(RequestBodyLimitLayer::new(LOCKFILE_BODY_LIMIT)),
+        )
+}
+
+/// Lockfiles of large workspaces easily exceed the global body limit.
+const LOCKFILE_BODY_LIMIT: usize = 8 * 1024 * 1024;
+
+async fn day_23_star() -> impl IntoResponse {
+    r#"<div id="star" class="lit"></div>"#
+}
+
+/// Present colors in cycle order; the last one wraps around to the first.
+const PRESENT_COLORS: [&str; 5] = ["red", "blue", "purple", "green", "gold"];
+
+/// The full color cycle starting at `color`, or `None` for an unknown color.
+fn day_23_present_chain_from(color: &str) -> Option<Vec<&'static str>> {
+    let start = PRESENT_COLORS.iter().position(|&known| known == color)?;
+    Some(
+        PRESENT_COLORS
+            .iter()
+            .cycle()
+            .skip(start)
+            .take(PRESENT_COLORS.len())
+            .copied()
+            .collect(),
+    )
+}
+
+async fn day_23_present_chain(Path(color): Path<String>) -> Response {
+    match day_23_present_chain_from(&color) {
+        Some(chain) => Json(chain).into_response(),
+        None => StatusCode::IM_A_TEAPOT.into_response(),
+    }
+}
+
+async fn day_23_present(Path(color): Path<String>) -> (StatusCode, String) {
+    let Some(index) = PRESENT_COLORS.iter().position(|&known| known == color) else {
+        return (StatusCode::IM_A_TEAPOT, "".to_string());
+    };
+    let next_color = PRESENT_COLORS[(index + 1) % PRESENT_COLORS.len()];
+    (StatusCode::OK, day_23_present_html(&color, next_color))
+}
+
+/// A present of `color` that turns into `next_color` when clicked.
+fn day_23_present_html(color: &str, next_color: &str) -> String {
+    // both colors come from the table today, but never trust what reaches an attribute
+    let color = htmlescape::encode_minimal(color);
+    let next_color = htmlescape::encode_minimal(next_color);
+    format!(
+        r#"<div class="present {color}" hx-get="/23/present/{next_color}" hx-swap="outerHTML">
+                <div class="ribbon"></div>
+                <div class="ribbon"></div>
+                <div class="ribbon"></div>
+                <div class="ribbon"></div>
+            </div>"#
+    )
+}
+
+#[derive(Deserialize)]
+struct OrnamentQuery {
+    delay: Option<String>,
+}
+
+/// Longest ornament animation delay accepted, in milliseconds.
+const MAX_ORNAMENT_DELAY_MS: u64 = 60_000;
+
+/// Parses a delay given as `500ms`, `2s` or a bare number of milliseconds into
+/// an htmx duration.
+fn day_23_ornament_delay(delay: &str) -> Option<String> {
+    let millis = match delay.strip_suffix("ms") {
+        Some(millis) => millis.parse::<u64>().ok()?,
+        None => match delay.strip_suffix('s') {
+            Some(secs) => secs.parse::<u64>().ok()?.checked_mul(1000)?,
+            None => delay.parse::<u64>().ok()?,
+        },
+    };
+    (millis <= MAX_ORNAMENT_DELAY_MS).then(|| format!("{millis}ms"))
+}
+
+async fn day_23_ornament(
+    Path((state, n)): Path<(String, String)>,
+    Query(query): Query<OrnamentQuery>,
+) -> (StatusCode, String) {
+    let next_state = match state.as_str() {
+        "on" => "off",
+        "off" => "on",
+        _ => {
+            return (StatusCode::IM_A_TEAPOT, "".to_string());
+        }
+    };
+    // a custom delay is passed on to the next toggle so it sticks
+    let (delay, next_query) = match query.delay.as_deref() {
+        None => ("2s".to_string(), String::new()),
+        Some(delay) => match day_23_ornament_delay(delay) {
+            Some(delay) => (delay.clone(), format!("?delay={delay}")),
+            None => {
+                return (StatusCode::IM_A_TEAPOT, "".to_string());
+            }
+        },
+    };
+    let n = htmlescape::encode_minimal(&n);
+    // changed is removed in hx-trigger
+    (
+        StatusCode::OK,
+        format!(
+            r#"<div class="ornament{}" id="ornament{n}" hx-trigger="load delay:{delay} once" hx-get="/23/ornament/{next_state}/{n}{next_query}" hx-swap="outerHTML"></div>"#,
+            if state == "on" { " on" } else { "" }
+        ),
+    )
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct LockfileChecksum {
+    color: i32,
+    top: u8,
+    left: u8,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ParseChecksumError {
+    TooShort { len: usize },
+    InvalidHex { segment: &'static str },
+}
+
+impl fmt::Display for ParseChecksumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseChecksumError::TooShort { len } => {
+                write!(f, "checksum is {len} characters long, expected at least 10")
+            }
+            ParseChecksumError::InvalidHex { segment } => {
+                write!(f, "checksum {segment} segment is not valid hex")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseChecksumError {}
+
+impl FromStr for LockfileChecksum {
+    type Err = ParseChecksumError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() < 10 {
+            return Err(ParseChecksumError::TooShort { len: s.len() });
+        }
+        let segment = |range: std::ops::Range<usize>, segment| {
+            // `get` also rejects multi-byte characters, which can't be hex anyway
+            s.get(range)
+                .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                .ok_or(ParseChecksumError::InvalidHex { segment })
+        };
+        let color = i32::from_str_radix(segment(0..6, "color")?, 16).expect("validated hex");
+        let top = u8::from_str_radix(segment(6..8, "top")?, 16).expect("validated hex");
+        let left = u8::from_str_radix(segment(8..10, "left")?, 16).expect("validated hex");
+        Ok(LockfileChecksum { color, top, left })
+    }
+}
+
+/// Reads the single `lockfile` multipart field into a string. Other fields are
+/// skipped, but a broken body is an error rather than the end of the form.
+/// Rejections are a 400, or a 413 past the body limit, whose body says what
+/// was wrong with the upload.
+async fn day_23_read_lockfile(mut multipart: Multipart) -> Result<String, Response> {
+    let bad_request = |msg: String| {
+        warn!("bad lockfile upload: {msg}");
+        (StatusCode::BAD_REQUEST, msg).into_response()
+    };
+    let malformed = |err: MultipartError| {
+        // a body over the route's limit is a 413, not a malformed form
+        if err.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            warn!("lockfile upload too large: {err}");
+            return (StatusCode::PAYLOAD_TOO_LARGE, err.body_text()).into_response();
+        }
+        bad_request(format!("malformed multipart: {err}"))
+    };
+    let mut lockfile = None;
+    while let Some(field) = multipart.next_field().await.map_err(malformed)? {
+        if field.name() != Some("lockfile") {
+            continue;
+        }
+        if lockfile.is_some() {
+            return Err(bad_request("more than one lockfile field".to_string()));
+        }
+        lockfile = Some(field.bytes().await.map_err(malformed)?);
+    }
+    let lockfile = lockfile.ok_or_else(|| bad_request("no lockfile field".to_string()))?;
+    String::from_utf8(lockfile.to_vec())
+        .map_err(|err| bad_request(format!("lockfile is not utf-8: {err}")))
+}
+
+/// The subset of the `Cargo.lock` format the lockfile endpoint cares about.
+#[derive(Deserialize)]
+struct Lockfile {
+    // missing rather than a parse error, so it can be reported as such
+    package: Option<Vec<LockfilePackage>>,
+}
+
+#[derive(Deserialize)]
+struct LockfilePackage {
+    // a package table without a name isn't a package, but mustn't fail the lockfile
+    name: Option<String>,
+    version: Option<String>,
+    // path and git dependencies have no checksum
+    checksum: Option<String>,
+}
+
+impl LockfilePackage {
+    /// The parsed checksum, or `None` for a package without one.
+    fn parsed_checksum(&self) -> Option<Result<LockfileChecksum, ParseChecksumError>> {
+        self.checksum.as_deref().map(LockfileChecksum::from_str)
+    }
+}
+
+/// Parses an uploaded lockfile; both rejections are a 400 saying what was wrong.
+fn day_23_parse_lockfile(body: &str) -> Result<Vec<LockfilePackage>, (StatusCode, String)> {
+    let lockfile = toml::from_str::<Lockfile>(body).map_err(|err| {
+        warn!("error parsing lockfile: {err}");
+        (
+            StatusCode::BAD_REQUEST,
+            format!("error parsing lockfile: {err}"),
+        )
+    })?;
+    lockfile.package.ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            "lockfile has no package array".to_string(),
+        )
+    })
+}
+
+#[derive(Deserialize)]
+struct LockfileQuery {
+    /// Only count the sprites instead of rendering them.
+    #[serde(default)]
+    count: bool,
+}
+
+async fn day_23_lockfile(
+    Query(query): Query<LockfileQuery>,
+    multipart: Multipart,
+) -> Result<Response, Response> {
+    let body = day_23_read_lockfile(multipart).await?;
+    let packages = day_23_parse_lockfile(&body).map_err(IntoResponse::into_response)?;
+    let mut response = String::new();
+    let mut sprites = 0;
+    for package in packages {
+        let Some(name) = &package.name else {
+            debug!("skipping package without a name");
+            continue;
+        };
+        let Some(checksum) = package.parsed_checksum() else {
+            debug!("no checksum for package {name}");
+            continue;
+        };
+        match checksum {
+            Ok(_) if query.count => sprites += 1,
+            Ok(entry) => {
+                response.push_str(&format!(
+                    r##"<div style="background-color:#{:06x};top:{}px;left:{}px;"></div>{}"##,
+                    entry.color, entry.top, entry.left, '\n'
+                ));
+            }
+            Err(err) => {
+                warn!("checksum parse error for package {name}: {err}");
+                return Ok(StatusCode::UNPROCESSABLE_ENTITY.into_response());
+            }
+        }
+    }
+    if query.count {
+        return Ok(Json(serde_json::json!({ "sprites": sprites })).into_response());
+    }
+    Ok(response.into_response())
+}
+
+#[derive(Serialize)]
+struct ChecksumReport {
+    name: Option<String>,
+    version: Option<String>,
+    valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+async fn day_23_lockfile_validate(
+    multipart: Multipart,
+) -> Result<Json<Vec<ChecksumReport>>, Response> {
+    let body = day_23_read_lockfile(multipart).await?;
+    let packages = day_23_parse_lockfile(&body).map_err(IntoResponse::into_response)?;
+    let report = packages
+        .into_iter()
+        .map(|package| {
+            let error = match package.parsed_checksum() {
+                Some(Ok(_)) => None,
+                Some(Err(err)) => Some(err.to_string()),
+                None => Some("checksum is missing".to_string()),
+            };
+            ChecksumReport {
+                name: package.name,
+                version: package.version,
+                valid: error.is_none(),
+                error,
+            }
+        })
+        .collect();
+    Ok(Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::{Body, to_bytes},
+        extract::Request,
+        http::header::CONTENT_TYPE,
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+
+    const BOUNDARY: &str = "lockfile-boundary";
+
+    fn part(name: &str, content: &str) -> String {
+        format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{content}\r\n"
+        )
+    }
+
+    async fn upload(body: String) -> (StatusCode, String) {
+        upload_to("/23/lockfile", body).await
+    }
+
+    async fn upload_to(uri: &str, body: String) -> (StatusCode, String) {
+        let response = upload_response(uri, body).await;
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    async fn upload_response(uri: &str, body: String) -> Response {
+        let request = Request::post(uri)
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(Body::from(body))
+            .unwrap();
+        router().oneshot(request).await.unwrap()
+    }
+
+    fn lockfile(toml: &str) -> String {
+        part("lockfile", toml) + &format!("--{BOUNDARY}--\r\n")
+    }
+
+    #[tokio::test]
+    async fn packages_without_a_checksum_or_name_are_skipped() {
+        let body = lockfile(
+            r#"
+[[package]]
+name = "workspace-member"
+version = "0.1.0"
+
+[[package]]
+name = "registry-crate"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "337b2c2d0f0d5bd8b8e8e3f8a9a5ee1b8c5e09b3d0c1a8e2b2c3d4e5f6a7b8c9"
+
+[[package]]
+version = "0.0.0"
+checksum = "ffffff0102"
+
+[[package]]
+name = "git-crate"
+version = "0.2.0"
+source = "git+https://example.com/git-crate#0123456789"
+"#,
+        );
+        assert_eq!(
+            upload(body).await,
+            (
+                StatusCode::OK,
+                r##"<div style="background-color:#337b2c;top:45px;left:15px;"></div>"##.to_string()
+                    + "\n"
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn an_invalid_checksum_is_unprocessable() {
+        let body = lockfile(
+            r#"
+[[package]]
+name = "path-crate"
+
+[[package]]
+name = "broken"
+checksum = "not-a-checksum"
+"#,
+        );
+        assert_eq!(upload(body).await.0, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn missing_lockfile_field_is_explained() {
+        let body = part("other", "x") + &format!("--{BOUNDARY}--\r\n");
+        assert_eq!(
+            upload(body).await,
+            (StatusCode::BAD_REQUEST, "no lockfile field".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn duplicate_lockfile_field_is_explained() {
+        let body = part("lockfile", "") + &part("lockfile", "") + &format!("--{BOUNDARY}--\r\n");
+        assert_eq!(
+            upload(body).await,
+            (
+                StatusCode::BAD_REQUEST,
+                "more than one lockfile field".to_string()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn truncated_body_is_explained() {
+        let body = format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"lockfile\"\r\n\r\n[[package]]"
+        );
+        let (status, message) = upload(body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(message.starts_with("malformed multipart: "), "{message}");
+    }
+
+    async fn get(uri: &str) -> (StatusCode, String) {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        let response = router().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    /// The color a present's `hx-get` leads to.
+    fn next_present(html: &str) -> &str {
+        let (_, rest) = html.split_once(r#"hx-get="/23/present/"#).unwrap();
+        rest.split('"').next().unwrap()
+    }
+
+    #[tokio::test]
+    async fn present_cycle_visits_each_color_once() {
+        let mut visited = vec![];
+        let mut color = "red".to_string();
+        for _ in 0..PRESENT_COLORS.len() {
+            let (status, html) = get(&format!("/23/present/{color}")).await;
+            assert_eq!(status, StatusCode::OK, "{color}");
+            assert!(html.starts_with(&format!(r#"<div class="present {color}""#)));
+            assert_eq!(html.matches(r#"<div class="ribbon"></div>"#).count(), 4);
+            visited.push(color);
+            color = next_present(&html).to_string();
+        }
+        assert_eq!(color, "red");
+        assert_eq!(visited, PRESENT_COLORS);
+
+        for unknown in ["pink", "Red"] {
+            let (status, _) = get(&format!("/23/present/{unknown}")).await;
+            assert_eq!(status, StatusCode::IM_A_TEAPOT, "{unknown}");
+        }
+    }
+
+    #[tokio::test]
+    async fn validate_reports_every_package() {
+        let (status, report) = upload_to(
+            "/23/lockfile/validate",
+            lockfile(
+                r#"
+[[package]]
+name = "good"
+version = "1.0.0"
+checksum = "337b2c2d0f"
+
+[[package]]
+name = "short"
+version = "0.1.0"
+checksum = "337b2c2d0"
+
+[[package]]
+name = "not-hex"
+version = "0.2.0"
+checksum = "33zb2c2d0f"
+
+[[package]]
+name = "local"
+version = "0.3.0"
+"#,
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+        assert_eq!(
+            report,
+            serde_json::json!([
+                {"name": "good", "version": "1.0.0", "valid": true},
+                {
+                    "name": "short",
+                    "version": "0.1.0",
+                    "valid": false,
+                    "error": "checksum is 9 characters long, expected at least 10"
+                },
+                {
+                    "name": "not-hex",
+                    "version": "0.2.0",
+                    "valid": false,
+                    "error": "checksum color segment is not valid hex"
+                },
+                {"name": "local", "version": "0.3.0", "valid": false, "error": "checksum is missing"},
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn validate_needs_a_package_array() {
+        let (status, message) = upload_to("/23/lockfile/validate", lockfile("version = 3")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(message, "lockfile has no package array");
+    }
+
+    #[tokio::test]
+    async fn both_endpoints_read_packages_alike() {
+        // a checksum that isn't a string breaks the lockfile, not just the package
+        let packages = "[[package]]\nname = \"a\"\nchecksum = 5\n";
+        for uri in ["/23/lockfile", "/23/lockfile/validate"] {
+            let (status, message) = upload_to(uri, lockfile(packages)).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+            assert!(
+                message.starts_with("error parsing lockfile"),
+                "{uri}: {message}"
+            );
+        }
+
+        // a package without a checksum is skipped by one and reported by the other
+        let packages = "[[package]]\nname = \"path\"\nversion = \"0.1.0\"\n";
+        let (status, body) = upload(lockfile(packages)).await;
+        assert_eq!((status, body.as_str()), (StatusCode::OK, ""));
+        let (status, report) = upload_to("/23/lockfile/validate", lockfile(packages)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            report,
+            r#"[{"name":"path","version":"0.1.0","valid":false,"error":"checksum is missing"}]"#
+        );
+    }
+
+    #[test]
+    fn checksum_errors_say_what_is_wrong() {
+        assert_eq!(
+            "337b2c2d0".parse::<LockfileChecksum>(),
+            Err(ParseChecksumError::TooShort { len: 9 })
+        );
+        assert_eq!(
+            "33zb2c2d0f".parse::<LockfileChecksum>(),
+            Err(ParseChecksumError::InvalidHex { segment: "color" })
+        );
+        assert_eq!(
+            "337b2cxx0f".parse::<LockfileChecksum>(),
+            Err(ParseChecksumError::InvalidHex { segment: "top" })
+        );
+        assert_eq!(
+            "337b2c2d0g".parse::<LockfileChecksum>(),
+            Err(ParseChecksumError::InvalidHex { segment: "left" })
+        );
+        // a multi-byte character straddling a segment boundary isn't hex either
+        assert_eq!(
+            "337b2é2d0f".parse::<LockfileChecksum>(),
+            Err(ParseChecksumError::InvalidHex { segment: "color" })
+        );
+        assert_eq!(
+            "337b2c2d0f".parse::<LockfileChecksum>(),
+            Ok(LockfileChecksum {
+                color: 0x337b2c,
+                top: 0x2d,
+                left: 0x0f
+            })
+        );
+        assert_eq!(
+            ParseChecksumError::InvalidHex { segment: "top" }.to_string(),
+            "checksum top segment is not valid hex"
+        );
+    }
+
+    #[test]
+    fn ornament_delays_are_normalized_to_milliseconds() {
+        assert_eq!(day_23_ornament_delay("500ms").as_deref(), Some("500ms"));
+        assert_eq!(day_23_ornament_delay("2s").as_deref(), Some("2000ms"));
+        assert_eq!(day_23_ornament_delay("750").as_deref(), Some("750ms"));
+        assert_eq!(day_23_ornament_delay("60s").as_deref(), Some("60000ms"));
+        for invalid in [
+            "61s",
+            "60001",
+            "soon",
+            "-5ms",
+            "1.5s",
+            "",
+            "99999999999999999999s",
+        ] {
+            assert_eq!(day_23_ornament_delay(invalid), None, "{invalid}");
+        }
+    }
+
+    #[tokio::test]
+    async fn ornament_delay_is_passed_on() {
+        let (status, html) = get("/23/ornament/on/1?delay=500ms").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            html,
+            r#"<div class="ornament on" id="ornament1" hx-trigger="load delay:500ms once" hx-get="/23/ornament/off/1?delay=500ms" hx-swap="outerHTML"></div>"#
+        );
+        let (_, html) = get("/23/ornament/off/1").await;
+        assert!(
+            html.contains(r#"hx-trigger="load delay:2s once""#),
+            "{html}"
+        );
+        assert!(html.contains(r#"hx-get="/23/ornament/on/1""#), "{html}");
+        for delay in ["61s", "soon"] {
+            let (status, _) = get(&format!("/23/ornament/on/1?delay={delay}")).await;
+            assert_eq!(status, StatusCode::IM_A_TEAPOT, "{delay}");
+        }
+    }
+
+    #[test]
+    fn present_colors_are_escaped() {
+        let html = day_23_present_html(r#"red" onclick="alert(1)"#, "<blue>");
+        assert!(
+            html.starts_with(
+                r#"<div class="present red&quot; onclick=&quot;alert(1)" hx-get="/23/present/&lt;blue&gt;""#
+            ),
+            "{html}"
+        );
+        assert!(!html.contains("<blue>"));
+    }
+
+    #[tokio::test]
+    async fn count_only_reports_the_sprites() {
+        let packages = r#"
+[[package]]
+name = "a"
+checksum = "337b2c2d0f"
+
+[[package]]
+name = "path"
+
+[[package]]
+name = "b"
+checksum = "ffffff0102"
+
+[[package]]
+name = "c"
+checksum = "0000000000"
+"#;
+        let (status, report) = upload_to("/23/lockfile?count=true", lockfile(packages)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report, r#"{"sprites":3}"#);
+        let response = upload_response("/23/lockfile?count=true", lockfile(packages)).await;
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+
+        let broken = format!("{packages}\n[[package]]\nname = \"d\"\nchecksum = \"nope\"\n");
+        let (status, _) = upload_to("/23/lockfile?count=true", lockfile(&broken)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let (status, _) = upload_to("/23/lockfile?count=true", lockfile("version = 3")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn chain_starts_at_any_color() {
+        for (start, color) in PRESENT_COLORS.iter().enumerate() {
+            let (status, chain) = get(&format!("/23/present/{color}/chain")).await;
+            assert_eq!(status, StatusCode::OK, "{color}");
+            let chain: Vec<String> = serde_json::from_str(&chain).unwrap();
+            assert_eq!(chain.len(), PRESENT_COLORS.len());
+            let expected = (0..PRESENT_COLORS.len())
+                .map(|step| PRESENT_COLORS[(start + step) % PRESENT_COLORS.len()])
+                .collect::<Vec<_>>();
+            assert_eq!(chain, expected);
+        }
+        assert_eq!(
+            day_23_present_chain_from("red"),
+            Some(vec!["red", "blue", "purple", "green", "gold"])
+        );
+        let (status, _) = get("/23/present/pink/chain").await;
+        assert_eq!(status, StatusCode::IM_A_TEAPOT);
+    }
+}
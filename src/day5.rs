@@ -0,0 +1,378 @@
+use std::str::FromStr;
+
+use axum::{
+    Json, Router,
+    body::{Body, Bytes},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header::CONTENT_TYPE},
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use cargo_manifest::{Manifest, MaybeInherited::Local};
+use jyt::{Converter, Ext};
+use semver::Version;
+use serde::Serialize;
+
+use crate::{msgpack, tar};
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/5/manifest", post(day_5_manifest))
+        .route("/5/validate", post(day_5_validate))
+}
+
+/// What `day_5_inspect` found in a manifest that parsed.
+struct ManifestSummary {
+    /// False only for a local package version that isn't SemVer.
+    valid_version: bool,
+    contains_magic_keyword: bool,
+    /// Well-formed `package.metadata.orders` entries as `item: quantity`.
+    orders: Vec<String>,
+}
+
+fn day_5_inspect(body: &str) -> Option<ManifestSummary> {
+    let manifest = Manifest::from_str(body).ok()?;
+    let package = manifest.package.as_ref();
+    // inherited versions live in the workspace root, which we don't have
+    let valid_version = match package.and_then(|package| package.version.as_ref()) {
+        Some(Local(version)) => Version::parse(version).is_ok(),
+        _ => true,
+    };
+    let contains_magic_keyword = match package.and_then(|package| package.keywords.as_ref()) {
+        Some(Local(keywords)) => keywords.iter().any(|keyword| keyword == "Christmas 2024"),
+        _ => false,
+    };
+    let mut orders = Vec::new();
+    if let Some(orders_array) = package
+        .and_then(|package| package.metadata.as_ref())
+        .and_then(|metadata| metadata.get("orders"))
+        .and_then(|orders| orders.as_array())
+    {
+        for order_item in orders_array {
+            if let (Some(toml::Value::String(item)), Some(toml::Value::Integer(quantity))) =
+                (order_item.get("item"), order_item.get("quantity"))
+            {
+                orders.push(format!("{}: {}", item, quantity));
+            }
+        }
+    }
+    Some(ManifestSummary {
+        valid_version,
+        contains_magic_keyword,
+        orders,
+    })
+}
+
+fn day_5_handle_toml(body: String) -> (StatusCode, Body) {
+    match day_5_inspect(&body) {
+        Some(summary) if !summary.valid_version => day_5_invalid_manifest_response(),
+        Some(summary) if !summary.contains_magic_keyword => day_5_magic_keyword_response(),
+        Some(summary) if summary.orders.is_empty() => day_5_no_content_response(),
+        Some(summary) => (StatusCode::OK, Body::from(summary.orders.join("\n"))),
+        None => day_5_invalid_manifest_response(),
+    }
+}
+
+fn day_5_no_content_response() -> (StatusCode, Body) {
+    (StatusCode::NO_CONTENT, Body::empty())
+}
+
+fn day_5_invalid_manifest_response() -> (StatusCode, Body) {
+    (StatusCode::BAD_REQUEST, Body::from("Invalid manifest"))
+}
+
+fn day_5_magic_keyword_response() -> (StatusCode, Body) {
+    (
+        StatusCode::BAD_REQUEST,
+        Body::from("Magic keyword not provided"),
+    )
+}
+
+fn day_5_unsupported_media_type_response() -> (StatusCode, Body) {
+    (StatusCode::UNSUPPORTED_MEDIA_TYPE, Body::empty())
+}
+
+/// The manifest formats accepted, by content type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ManifestFormat {
+    Toml,
+    Json,
+    Yaml,
+    Msgpack,
+    Tar,
+}
+
+impl ManifestFormat {
+    /// The format named by the `Content-Type` header, if it is one we accept.
+    fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        match headers.get(CONTENT_TYPE)?.to_str().ok()? {
+            "application/toml" => Some(Self::Toml),
+            "application/json" => Some(Self::Json),
+            "application/yaml" => Some(Self::Yaml),
+            "application/msgpack" => Some(Self::Msgpack),
+            "application/x-tar" => Some(Self::Tar),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Toml => "toml",
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+            Self::Msgpack => "msgpack",
+            Self::Tar => "tar",
+        }
+    }
+}
+
+/// Converts the body to TOML, failing for a body that doesn't convert.
+fn day_5_to_toml(format: ManifestFormat, body: Bytes) -> Result<String, ()> {
+    let ext = match format {
+        // MessagePack and tarballs are binary; every other format has to be UTF-8
+        ManifestFormat::Msgpack => {
+            let value = msgpack::from_slice(&body).map_err(|_| ())?;
+            return toml::to_string(&value).map_err(|_| ());
+        }
+        ManifestFormat::Tar => return day_5_manifest_from_tar(&body),
+        ManifestFormat::Toml => None,
+        ManifestFormat::Json => Some(Ext::Json),
+        ManifestFormat::Yaml => Some(Ext::Yaml),
+    };
+    let body = String::from_utf8(body.to_vec()).map_err(|_| ())?;
+    match ext {
+        Some(ext) => body.to_toml(ext).map_err(|_| ()),
+        None => Ok(body),
+    }
+}
+
+/// The top-most `Cargo.toml` in a tarball, e.g. the one `cargo package` puts
+/// in the crate's directory rather than a vendored dependency's.
+fn day_5_manifest_from_tar(body: &[u8]) -> Result<String, ()> {
+    let files = tar::files(body).map_err(|_| ())?;
+    let manifest = files
+        .into_iter()
+        .map(|entry| (entry.path.trim_start_matches("./").to_string(), entry.data))
+        .filter(|(path, _)| path.rsplit('/').next() == Some("Cargo.toml"))
+        .min_by_key(|(path, _)| path.matches('/').count())
+        .ok_or(())?;
+    String::from_utf8(manifest.1.to_vec()).map_err(|_| ())
+}
+
+/// Names the format `/5/manifest` read the body as, whatever the outcome.
+const X_MANIFEST_FORMAT: HeaderName = HeaderName::from_static("x-manifest-format");
+
+async fn day_5_manifest(headers: HeaderMap, body: Bytes) -> Response {
+    let Some(format) = ManifestFormat::from_headers(&headers) else {
+        return day_5_unsupported_media_type_response().into_response();
+    };
+    let mut response = match day_5_to_toml(format, body) {
+        Ok(toml) => day_5_handle_toml(toml),
+        Err(()) => day_5_invalid_manifest_response(),
+    }
+    .into_response();
+    response
+        .headers_mut()
+        .insert(X_MANIFEST_FORMAT, HeaderValue::from_static(format.as_str()));
+    response
+}
+
+#[derive(Serialize)]
+struct ManifestReport {
+    parsed: bool,
+    valid_version: bool,
+    contains_magic_keyword: bool,
+    orders: usize,
+}
+
+async fn day_5_validate(
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<ManifestReport>, (StatusCode, Body)> {
+    let Some(format) = ManifestFormat::from_headers(&headers) else {
+        return Err(day_5_unsupported_media_type_response());
+    };
+    let summary = day_5_to_toml(format, body)
+        .ok()
+        .and_then(|toml| day_5_inspect(&toml));
+    Ok(Json(match summary {
+        Some(summary) => ManifestReport {
+            parsed: true,
+            valid_version: summary.valid_version,
+            contains_magic_keyword: summary.contains_magic_keyword,
+            orders: summary.orders.len(),
+        },
+        None => ManifestReport {
+            parsed: false,
+            valid_version: false,
+            contains_magic_keyword: false,
+            orders: 0,
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::to_bytes, extract::Request};
+    use serde_json::{Value, json};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn send(content_type: &str, body: Vec<u8>) -> (StatusCode, HeaderMap, String) {
+        let request = Request::post("/5/manifest")
+            .header(CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap();
+        let response = router().oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let body = to_bytes(body, usize::MAX).await.unwrap();
+        (
+            parts.status,
+            parts.headers,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    /// Encodes the small values a manifest needs as MessagePack.
+    fn msgpack(value: &Value) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match value {
+            Value::Bool(value) => bytes.push(if *value { 0xc3 } else { 0xc2 }),
+            Value::Number(number) => {
+                bytes.push(0xcf);
+                bytes.extend(number.as_u64().unwrap().to_be_bytes());
+            }
+            Value::String(string) => {
+                bytes.extend([0xd9, string.len() as u8]);
+                bytes.extend(string.as_bytes());
+            }
+            Value::Array(items) => {
+                bytes.push(0x90 | items.len() as u8);
+                items.iter().for_each(|item| bytes.extend(msgpack(item)));
+            }
+            Value::Object(map) => {
+                bytes.push(0x80 | map.len() as u8);
+                for (key, value) in map {
+                    bytes.extend(msgpack(&Value::from(key.as_str())));
+                    bytes.extend(msgpack(value));
+                }
+            }
+            Value::Null => bytes.push(0xc0),
+        }
+        bytes
+    }
+
+    fn manifest() -> Value {
+        json!({
+            "package": {
+                "name": "not-a-gift-order",
+                "authors": ["Not Santa"],
+                "keywords": ["Christmas 2024"],
+                "metadata": {
+                    "orders": [
+                        {"item": "Toy car", "quantity": 2},
+                        {"item": "Lego brick", "quantity": 230},
+                    ],
+                },
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn msgpack_manifest_is_read_like_json() {
+        let (status, headers, body) = send("application/msgpack", msgpack(&manifest())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[X_MANIFEST_FORMAT], "msgpack");
+        assert_eq!(body, "Toy car: 2\nLego brick: 230");
+        let (status, headers, json) =
+            send("application/json", manifest().to_string().into_bytes()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[X_MANIFEST_FORMAT], "json");
+        assert_eq!(json, body);
+    }
+
+    #[tokio::test]
+    async fn unsupported_media_type_has_no_format_header() {
+        let (status, headers, _) = send("text/plain", b"[package]".to_vec()).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert!(headers.get(X_MANIFEST_FORMAT).is_none());
+    }
+
+    #[tokio::test]
+    async fn malformed_msgpack_is_an_invalid_manifest() {
+        let mut truncated = msgpack(&manifest());
+        truncated.truncate(truncated.len() / 2);
+        let (status, _, body) = send("application/msgpack", truncated).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body, "Invalid manifest");
+    }
+
+    #[tokio::test]
+    async fn tarball_manifest_is_the_top_most_cargo_toml() {
+        let manifest = r#"
+            [package]
+            name = "not-a-gift-order"
+            keywords = ["Christmas 2024"]
+
+            [[package.metadata.orders]]
+            item = "Toy car"
+            quantity = 2
+        "#;
+        let tarball = tar::tests::archive(&[
+            tar::tests::entry("gift/vendor/dep/Cargo.toml", b'0', b"[package"),
+            tar::tests::entry("gift/Cargo.toml", b'0', manifest.as_bytes()),
+        ]);
+        let (status, headers, body) = send("application/x-tar", tarball).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[X_MANIFEST_FORMAT], "tar");
+        assert_eq!(body, "Toy car: 2");
+    }
+
+    #[tokio::test]
+    async fn tarball_without_a_manifest_is_invalid() {
+        let tarball = tar::tests::archive(&[tar::tests::entry("gift/README.md", b'0', b"hi")]);
+        let (status, _, body) = send("application/x-tar", tarball).await;
+        assert_eq!(
+            (status, body.as_str()),
+            (StatusCode::BAD_REQUEST, "Invalid manifest")
+        );
+    }
+
+    fn toml_manifest(version: &str, keywords: &str) -> Vec<u8> {
+        format!(
+            r#"
+            [package]
+            name = "not-a-gift-order"
+            version = "{version}"
+            keywords = [{keywords}]
+
+            [[package.metadata.orders]]
+            item = "Toy car"
+            quantity = 2
+        "#
+        )
+        .into_bytes()
+    }
+
+    #[tokio::test]
+    async fn non_semver_version_is_an_invalid_manifest() {
+        let manifest = toml_manifest("banana", r#""Christmas 2024""#);
+        let (status, _, body) = send("application/toml", manifest).await;
+        assert_eq!(
+            (status, body.as_str()),
+            (StatusCode::BAD_REQUEST, "Invalid manifest")
+        );
+    }
+
+    #[tokio::test]
+    async fn semver_version_goes_on_to_keywords_and_orders() {
+        let manifest = toml_manifest("1.2.3", r#""Christmas 2024""#);
+        let (status, _, body) = send("application/toml", manifest).await;
+        assert_eq!((status, body.as_str()), (StatusCode::OK, "Toy car: 2"));
+        let (status, _, body) = send("application/toml", toml_manifest("1.2.3", "")).await;
+        assert_eq!(
+            (status, body.as_str()),
+            (StatusCode::BAD_REQUEST, "Magic keyword not provided")
+        );
+    }
+}
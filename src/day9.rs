@@ -0,0 +1,705 @@
+use std::{
+    env,
+    str::FromStr,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Router,
+    body::Body,
+    extract::{Query, State},
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{CONTENT_TYPE, RETRY_AFTER},
+    },
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use leaky_bucket::RateLimiter;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+use tracing::*;
+
+use crate::metrics;
+
+pub fn router() -> Router {
+    day_9_routes(Day9AppState::new(
+        BucketConfig::from_env(),
+        ConversionConfig::from_env(),
+    ))
+}
+
+fn day_9_routes(state: Day9AppState) -> Router {
+    Router::new()
+        .route("/9/milk", post(day_9_milk))
+        .route("/9/refill", post(day_9_refill))
+        .route("/9/status", get(day_9_status))
+        .route("/9/withdraw", post(day_9_withdraw))
+        .with_state(Arc::new(state))
+}
+
+struct Day9AppState {
+    bucket: BucketConfig,
+    limiter: Mutex<RateLimiter>,
+    // `RateLimiter` doesn't expose its balance, so mirror it here. Both fields
+    // are only updated while `limiter` is locked.
+    available: AtomicUsize,
+    refilled_at: Mutex<Instant>,
+    conversion: ConversionConfig,
+}
+
+impl Day9AppState {
+    fn new(bucket: BucketConfig, conversion: ConversionConfig) -> Self {
+        metrics::MILK_AVAILABLE.store(bucket.max, Ordering::SeqCst);
+        Self {
+            bucket,
+            limiter: Mutex::new(bucket.rate_limiter(bucket.max)),
+            available: AtomicUsize::new(bucket.max),
+            refilled_at: Mutex::new(Instant::now()),
+            conversion,
+        }
+    }
+
+    /// Tries to take `count` tokens from the bucket, keeping `available` in
+    /// step. Returns the tokens left on success.
+    async fn withdraw(&self, count: usize) -> Option<usize> {
+        let limiter = self.limiter.lock().await;
+        self.sync_available().await;
+        if !limiter.try_acquire(count) {
+            return None;
+        }
+        let _ = self
+            .available
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |available| {
+                Some(available.saturating_sub(count))
+            });
+        let remaining = self.available.load(Ordering::SeqCst);
+        metrics::MILK_AVAILABLE.store(remaining, Ordering::SeqCst);
+        Some(remaining)
+    }
+
+    /// Resets the bucket to hold `tokens`, at most `max`.
+    async fn refill(&self, tokens: usize) {
+        let tokens = tokens.min(self.bucket.max);
+        let mut limiter = self.limiter.lock().await;
+        *limiter = self.bucket.rate_limiter(tokens);
+        *self.refilled_at.lock().await = Instant::now();
+        self.available.store(tokens, Ordering::SeqCst);
+        metrics::MILK_AVAILABLE.store(tokens, Ordering::SeqCst);
+    }
+
+    /// How long until `count` tokens could be withdrawn, given the tokens the
+    /// bucket holds now.
+    async fn retry_after(&self, count: usize) -> Duration {
+        let _limiter = self.limiter.lock().await;
+        let available = self.sync_available().await;
+        let missing = count.saturating_sub(available) as u32;
+        if missing == 0 {
+            return Duration::ZERO;
+        }
+        // `sync_available` leaves `refilled_at` less than an interval ago
+        let until_next = self
+            .bucket
+            .interval
+            .saturating_sub(self.refilled_at.lock().await.elapsed());
+        until_next + self.bucket.interval * (missing - 1)
+    }
+
+    async fn status(&self) -> usize {
+        let _limiter = self.limiter.lock().await;
+        self.sync_available().await
+    }
+
+    /// Credits the tokens the limiter has refilled since the last sync.
+    async fn sync_available(&self) -> usize {
+        let mut refilled_at = self.refilled_at.lock().await;
+        let ticks = refilled_at
+            .elapsed()
+            .div_duration_f64(self.bucket.interval)
+            .floor() as u32;
+        if ticks > 0 {
+            *refilled_at += self.bucket.interval * ticks;
+            let _ = self
+                .available
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |available| {
+                    Some((available + ticks as usize).min(self.bucket.max))
+                });
+        }
+        let available = self.available.load(Ordering::SeqCst);
+        metrics::MILK_AVAILABLE.store(available, Ordering::SeqCst);
+        available
+    }
+}
+
+/// Bucket shape, overridable through `MILK_BUCKET_MAX` and `MILK_REFILL_MS`.
+#[derive(Clone, Copy)]
+struct BucketConfig {
+    max: usize,
+    interval: Duration,
+}
+
+impl Default for BucketConfig {
+    fn default() -> Self {
+        Self {
+            max: 5,
+            interval: Duration::from_secs(1),
+        }
+    }
+}
+
+impl BucketConfig {
+    fn from_env() -> Self {
+        let defaults = Self::default();
+        let max = match day_9_env_or("MILK_BUCKET_MAX", defaults.max) {
+            0 => {
+                warn!("MILK_BUCKET_MAX must be positive, using default");
+                defaults.max
+            }
+            max => max,
+        };
+        let interval = match day_9_env_or("MILK_REFILL_MS", defaults.interval.as_millis() as u64) {
+            0 => {
+                warn!("MILK_REFILL_MS must be positive, using default");
+                defaults.interval
+            }
+            millis => Duration::from_millis(millis),
+        };
+        Self { max, interval }
+    }
+
+    fn rate_limiter(&self, initial: usize) -> RateLimiter {
+        RateLimiter::builder()
+            .max(self.max)
+            .initial(initial)
+            .interval(self.interval)
+            .build()
+    }
+}
+
+/// Unit conversion factors, overridable through `MILK_LITERS_PER_GALLON`,
+/// `MILK_PINTS_PER_LITRE` and `MILK_MILLILITERS_PER_TABLESPOON`, and the
+/// number of decimal places results are rounded to (`MILK_DECIMALS`).
+struct ConversionConfig {
+    liters_per_gallon: f64,
+    pints_per_litre: f64,
+    milliliters_per_tablespoon: f64,
+    decimals: u32,
+}
+
+impl Default for ConversionConfig {
+    fn default() -> Self {
+        Self {
+            liters_per_gallon: 3.78541253,
+            pints_per_litre: 1.7598,
+            milliliters_per_tablespoon: 14.7867648,
+            decimals: 6,
+        }
+    }
+}
+
+impl ConversionConfig {
+    /// Every unit accepted in a conversion request body.
+    const UNITS: [&str; 6] = [
+        "liters",
+        "gallons",
+        "litres",
+        "pints",
+        "tablespoons",
+        "milliliters",
+    ];
+
+    /// Beyond this an `f64` has no more significant decimal digits to round.
+    const MAX_DECIMALS: u32 = 15;
+
+    fn from_env() -> Self {
+        let defaults = Self::default();
+        let decimals = match day_9_env_or("MILK_DECIMALS", defaults.decimals) {
+            decimals if decimals > Self::MAX_DECIMALS => {
+                warn!(
+                    "MILK_DECIMALS must be at most {}, using default",
+                    Self::MAX_DECIMALS
+                );
+                defaults.decimals
+            }
+            decimals => decimals,
+        };
+        let factor = |name, default| {
+            day_9_positive_factor(name, day_9_env_or(name, default)).unwrap_or(default)
+        };
+        Self {
+            liters_per_gallon: factor("MILK_LITERS_PER_GALLON", defaults.liters_per_gallon),
+            pints_per_litre: factor("MILK_PINTS_PER_LITRE", defaults.pints_per_litre),
+            milliliters_per_tablespoon: factor(
+                "MILK_MILLILITERS_PER_TABLESPOON",
+                defaults.milliliters_per_tablespoon,
+            ),
+            decimals,
+        }
+    }
+
+    /// Milliliters in one `unit`; every conversion goes through milliliters.
+    fn milliliters_per(&self, unit: &str) -> Option<f64> {
+        match unit {
+            "liters" | "litres" => Some(1000.0),
+            "gallons" => Some(self.liters_per_gallon * 1000.0),
+            "pints" => Some(1000.0 / self.pints_per_litre),
+            "tablespoons" => Some(self.milliliters_per_tablespoon),
+            "milliliters" => Some(1.0),
+            _ => None,
+        }
+    }
+
+    /// The unit `unit` converts to when the request doesn't name a target.
+    fn counterpart(unit: &str) -> Option<&'static str> {
+        match unit {
+            "liters" => Some("gallons"),
+            "gallons" => Some("liters"),
+            "litres" => Some("pints"),
+            "pints" => Some("litres"),
+            "tablespoons" => Some("milliliters"),
+            "milliliters" => Some("tablespoons"),
+            _ => None,
+        }
+    }
+
+    /// Converts `value` given in `unit` to `to`, or to its counterpart unit,
+    /// rounded to `decimals` places. Negative and non-finite amounts aren't
+    /// milk, and converting a unit to itself is refused.
+    fn convert(&self, unit: &str, value: f64, to: Option<&str>) -> Option<(&'static str, f64)> {
+        if !value.is_finite() || value < 0.0 {
+            return None;
+        }
+        let target = match to {
+            Some(to) => *Self::UNITS.iter().find(|&&target| target == to)?,
+            None => Self::counterpart(unit)?,
+        };
+        if target == unit {
+            return None;
+        }
+        let converted = value * self.milliliters_per(unit)? / self.milliliters_per(target)?;
+        let factor = 10f64.powi(self.decimals as i32);
+        let rounded = (converted * factor).round() / factor;
+        // huge amounts overflow when scaled, and are too coarse to need rounding
+        let converted = if rounded.is_finite() {
+            rounded
+        } else {
+            converted
+        };
+        converted.is_finite().then_some((target, converted))
+    }
+}
+
+fn day_9_env_or<T: FromStr>(name: &str, default: T) -> T {
+    match env::var(name) {
+        Ok(value) => value.parse().unwrap_or_else(|_| {
+            warn!("invalid value {value:?} for {name}, using default");
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+/// `factor` if it can scale a conversion: a zero, negative or non-finite
+/// factor would turn every result into garbage, so it is warned about.
+fn day_9_positive_factor(name: &str, factor: f64) -> Option<f64> {
+    if factor.is_finite() && factor > 0.0 {
+        Some(factor)
+    } else {
+        warn!("{name} must be positive, using default");
+        None
+    }
+}
+
+fn day_9_bad_request() -> (StatusCode, Body) {
+    (StatusCode::BAD_REQUEST, Body::empty())
+}
+
+#[derive(Deserialize)]
+struct MilkQuery {
+    /// Answer conversions as `{"value": .., "unit": ..}` instead of keying the
+    /// value by its unit.
+    #[serde(default)]
+    label: bool,
+}
+
+#[derive(Deserialize)]
+struct MilkWithdrawal {
+    count: usize,
+}
+
+async fn day_9_milk(
+    State(state): State<Arc<Day9AppState>>,
+    Query(query): Query<MilkQuery>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    match headers.get("content-type") {
+        Some(content_type) if content_type == HeaderValue::from_static("application/json") => {
+            state.withdraw(1).await;
+            match body.parse::<serde_json::Value>() {
+                Ok(json) => {
+                    let mut units = ConversionConfig::UNITS
+                        .iter()
+                        .filter_map(|&unit| json.get(unit).map(|value| (unit, value)));
+                    // `to` picks another target than the unit's counterpart
+                    let to = match json.get("to") {
+                        None => None,
+                        Some(serde_json::Value::String(to)) => Some(to.as_str()),
+                        Some(_) => return day_9_bad_request().into_response(),
+                    };
+                    match (units.next(), units.next()) {
+                        (Some((unit, value)), None) => match value
+                            .as_f64()
+                            .and_then(|value| state.conversion.convert(unit, value, to))
+                        {
+                            Some((target, converted)) => {
+                                let mut data = json::JsonValue::new_object();
+                                if query.label {
+                                    data["value"] = converted.into();
+                                    data["unit"] = target.into();
+                                } else {
+                                    data[target] = converted.into();
+                                }
+                                (StatusCode::OK, Body::from(data.dump())).into_response()
+                            }
+                            None => day_9_bad_request().into_response(),
+                        },
+                        _ => day_9_bad_request().into_response(),
+                    }
+                }
+                Err(_) => day_9_bad_request().into_response(),
+            }
+        }
+        _ => {
+            // an empty body keeps the legacy single-token withdrawal
+            let count = if body.trim().is_empty() {
+                1
+            } else {
+                match serde_json::from_str::<MilkWithdrawal>(&body) {
+                    Ok(withdrawal) => withdrawal.count,
+                    Err(_) => return day_9_bad_request().into_response(),
+                }
+            };
+            if count == 0 || count > state.bucket.max {
+                return day_9_bad_request().into_response();
+            }
+            if state.withdraw(count).await.is_some() {
+                (StatusCode::OK, Body::from("Milk withdrawn\n")).into_response()
+            } else {
+                // Retry-After is in whole seconds, so round up
+                let retry_after = state.retry_after(count).await.as_secs_f64().ceil().max(1.0);
+                (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(RETRY_AFTER, (retry_after as u64).to_string())],
+                    Body::from("No milk available\n"),
+                )
+                    .into_response()
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct MilkRefill {
+    tokens: usize,
+}
+
+async fn day_9_refill(State(state): State<Arc<Day9AppState>>, body: String) -> Response {
+    // an empty body fills the bucket to the brim
+    let tokens = if body.trim().is_empty() {
+        state.bucket.max
+    } else {
+        match serde_json::from_str::<MilkRefill>(&body) {
+            Ok(refill) if refill.tokens <= state.bucket.max => refill.tokens,
+            _ => return day_9_bad_request().into_response(),
+        }
+    };
+    state.refill(tokens).await;
+    "".into_response()
+}
+
+async fn day_9_status(State(state): State<Arc<Day9AppState>>) -> impl IntoResponse {
+    let mut data = json::JsonValue::new_object();
+    data["available"] = state.status().await.into();
+    data["max"] = state.bucket.max.into();
+    ([(CONTENT_TYPE, "application/json")], data.dump())
+}
+
+async fn day_9_withdraw(State(state): State<Arc<Day9AppState>>) -> impl IntoResponse {
+    let remaining = state.withdraw(1).await;
+    let mut data = json::JsonValue::new_object();
+    data["withdrawn"] = remaining.is_some().into();
+    data["remaining"] = remaining.unwrap_or(0).into();
+    let status = if remaining.is_some() {
+        StatusCode::OK
+    } else {
+        StatusCode::TOO_MANY_REQUESTS
+    };
+    (status, [(CONTENT_TYPE, "application/json")], data.dump())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::extract::Request;
+    use serde_json::{Value, json};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn app() -> Router {
+        app_with(BucketConfig::default())
+    }
+
+    fn app_with(bucket: BucketConfig) -> Router {
+        day_9_routes(Day9AppState::new(bucket, ConversionConfig::default()))
+    }
+
+    /// A bucket of `max` tokens that won't refill during a test.
+    fn slow_bucket(max: usize) -> BucketConfig {
+        BucketConfig {
+            max,
+            interval: Duration::from_secs(60),
+        }
+    }
+
+    /// Withdraws from `/9/milk` without a conversion content type.
+    async fn milk(app: &Router, body: &str) -> (StatusCode, HeaderMap, String) {
+        let request = Request::post("/9/milk")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        send(app.clone(), request).await
+    }
+
+    async fn send(app: Router, request: Request) -> (StatusCode, HeaderMap, String) {
+        let response = app.oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        (
+            parts.status,
+            parts.headers,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    /// Posts a conversion request to `uri`, returning the status and the
+    /// parsed JSON answer, if any.
+    async fn convert(uri: &str, body: &str) -> (StatusCode, Option<Value>) {
+        let request = Request::post(uri)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let (status, _, body) = send(app(), request).await;
+        (status, serde_json::from_str(&body).ok())
+    }
+
+    #[tokio::test]
+    async fn tablespoons_round_trip_through_milliliters() {
+        let (status, milliliters) = convert("/9/milk", r#"{"tablespoons": 2}"#).await;
+        assert_eq!(status, StatusCode::OK);
+        let milliliters = milliliters.unwrap();
+        assert_eq!(milliliters, json!({ "milliliters": 29.57353 }));
+
+        let (status, tablespoons) = convert("/9/milk", &milliliters.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(tablespoons.unwrap()["tablespoons"].as_f64(), Some(2.0));
+    }
+
+    #[tokio::test]
+    async fn more_than_one_unit_is_rejected() {
+        let (status, _) = convert("/9/milk", r#"{"tablespoons": 1, "milliliters": 1}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn unusable_factors_fall_back_to_the_default() {
+        assert_eq!(day_9_positive_factor("F", 4.5), Some(4.5));
+        for factor in [0.0, -3.78, f64::NAN, f64::INFINITY] {
+            assert_eq!(day_9_positive_factor("F", factor), None, "{factor}");
+        }
+    }
+
+    #[tokio::test]
+    async fn milk_honors_a_custom_capacity() {
+        let app = app_with(slow_bucket(2));
+        for _ in 0..2 {
+            let (status, _, body) = milk(&app, "").await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body, "Milk withdrawn\n");
+        }
+        let (status, _, body) = milk(&app, "").await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(body, "No milk available\n");
+    }
+
+    #[tokio::test]
+    async fn bulk_withdrawals_take_all_tokens_or_none() {
+        let app = app_with(slow_bucket(5));
+        assert_eq!(milk(&app, r#"{"count": 3}"#).await.0, StatusCode::OK);
+        // two left, so three can't be taken and none are
+        assert_eq!(
+            milk(&app, r#"{"count": 3}"#).await.0,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(milk(&app, r#"{"count": 2}"#).await.0, StatusCode::OK);
+        assert_eq!(milk(&app, "").await.0, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn bulk_withdrawals_are_bounded_by_the_capacity() {
+        let app = app_with(slow_bucket(5));
+        for body in [
+            r#"{"count": 0}"#,
+            r#"{"count": 6}"#,
+            r#"{"count": -1}"#,
+            "milk",
+        ] {
+            assert_eq!(milk(&app, body).await.0, StatusCode::BAD_REQUEST, "{body}");
+        }
+        assert_eq!(milk(&app, r#"{"count": 5}"#).await.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn conversions_are_rounded() {
+        let (status, gallons) = convert("/9/milk", r#"{"liters": 1}"#).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(gallons.unwrap()["gallons"].as_f64(), Some(0.264172));
+        let (_, liters) = convert("/9/milk", r#"{"gallons": 1}"#).await;
+        assert_eq!(liters.unwrap()["liters"].as_f64(), Some(3.785413));
+    }
+
+    #[tokio::test]
+    async fn negative_and_non_finite_amounts_are_rejected() {
+        for body in [
+            r#"{"liters": -1}"#,
+            r#"{"liters": NaN}"#,
+            r#"{"liters": "1"}"#,
+        ] {
+            assert_eq!(
+                convert("/9/milk", body).await.0,
+                StatusCode::BAD_REQUEST,
+                "{body}"
+            );
+        }
+        let conversion = ConversionConfig::default();
+        for value in [f64::NAN, f64::INFINITY, -0.5] {
+            assert_eq!(conversion.convert("liters", value, None), None, "{value}");
+        }
+    }
+
+    #[tokio::test]
+    async fn throttled_withdrawals_say_when_to_retry() {
+        let app = app_with(BucketConfig {
+            max: 1,
+            interval: Duration::from_secs(2),
+        });
+        let (_, headers, _) = milk(&app, "").await;
+        assert!(headers.get(RETRY_AFTER).is_none());
+        let (status, headers, _) = milk(&app, "").await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = headers[RETRY_AFTER].to_str().unwrap().parse().unwrap();
+        assert!((1..=2).contains(&retry_after), "{retry_after}");
+    }
+
+    async fn withdraw(app: &Router) -> (StatusCode, Value) {
+        let request = Request::post("/9/withdraw").body(Body::empty()).unwrap();
+        let (status, headers, body) = send(app.clone(), request).await;
+        assert_eq!(headers[CONTENT_TYPE], "application/json");
+        (status, serde_json::from_str(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn withdraw_reports_the_remaining_tokens() {
+        let app = app_with(slow_bucket(2));
+        assert_eq!(
+            withdraw(&app).await,
+            (StatusCode::OK, json!({ "withdrawn": true, "remaining": 1 }))
+        );
+        assert_eq!(
+            withdraw(&app).await,
+            (StatusCode::OK, json!({ "withdrawn": true, "remaining": 0 }))
+        );
+        assert_eq!(
+            withdraw(&app).await,
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                json!({ "withdrawn": false, "remaining": 0 })
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn cross_unit_conversions() {
+        for (body, unit, expected) in [
+            (r#"{"liters": 1, "to": "pints"}"#, "pints", 1.7598),
+            (r#"{"gallons": 1, "to": "litres"}"#, "litres", 3.785413),
+            (r#"{"pints": 1.7598, "to": "liters"}"#, "liters", 1.0),
+            (
+                r#"{"gallons": 1, "to": "tablespoons"}"#,
+                "tablespoons",
+                256.00005,
+            ),
+        ] {
+            let (status, converted) = convert("/9/milk", body).await;
+            assert_eq!(status, StatusCode::OK, "{body}");
+            assert_eq!(converted.unwrap()[unit].as_f64(), Some(expected), "{body}");
+        }
+        for body in [
+            r#"{"liters": 1, "to": "liters"}"#,
+            r#"{"liters": 1, "to": "cups"}"#,
+            r#"{"liters": 1, "to": 5}"#,
+        ] {
+            assert_eq!(
+                convert("/9/milk", body).await.0,
+                StatusCode::BAD_REQUEST,
+                "{body}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn labeled_conversions_name_the_unit() {
+        let (status, labeled) = convert("/9/milk?label=true", r#"{"liters": 1}"#).await;
+        assert_eq!(status, StatusCode::OK);
+        let labeled = labeled.unwrap();
+        assert_eq!(labeled["unit"], "gallons");
+        assert_eq!(labeled["value"].as_f64(), Some(0.264172));
+        assert_eq!(labeled.as_object().unwrap().len(), 2);
+    }
+
+    async fn refill(app: &Router, body: &str) -> StatusCode {
+        let request = Request::post("/9/refill")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        send(app.clone(), request).await.0
+    }
+
+    async fn status(app: &Router) -> Value {
+        let request = Request::get("/9/status").body(Body::empty()).unwrap();
+        serde_json::from_str(&send(app.clone(), request).await.2).unwrap()
+    }
+
+    #[tokio::test]
+    async fn refill_restores_a_given_level() {
+        let app = app_with(slow_bucket(5));
+        assert_eq!(milk(&app, r#"{"count": 5}"#).await.0, StatusCode::OK);
+        assert_eq!(status(&app).await, json!({ "available": 0, "max": 5 }));
+
+        assert_eq!(refill(&app, r#"{"tokens": 2}"#).await, StatusCode::OK);
+        assert_eq!(status(&app).await, json!({ "available": 2, "max": 5 }));
+        assert_eq!(
+            milk(&app, r#"{"count": 3}"#).await.0,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+
+        assert_eq!(
+            refill(&app, r#"{"tokens": 6}"#).await,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(refill(&app, "").await, StatusCode::OK);
+        assert_eq!(status(&app).await, json!({ "available": 5, "max": 5 }));
+    }
+}
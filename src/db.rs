@@ -0,0 +1,230 @@
+use std::{env, time::Duration};
+
+use sqlx::{
+    Executor, PgPool,
+    migrate::{Migrate, MigrateError, Migrator},
+    postgres::PgPoolOptions,
+};
+use tracing::*;
+
+/// Most connections the pool opens, overridable through `DB_MAX_CONNECTIONS`.
+const DEFAULT_MAX_CONNECTIONS: u32 = 10;
+/// How long a handler waits for a free connection, overridable through
+/// `DB_ACQUIRE_TIMEOUT_MS`.
+const DEFAULT_ACQUIRE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest a single statement may run, overridable through
+/// `DB_STATEMENT_TIMEOUT_MS`, so one slow query can't hold a connection hostage.
+const DEFAULT_STATEMENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Schema migrations from `migrations/`, applied in order at startup. They
+/// are written to be idempotent, since databases created before the migrator
+/// already have the tables.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// How often an operation is tried and how long to wait in between.
+#[derive(Clone, Copy)]
+struct RetryPolicy {
+    /// Names the operation in log messages.
+    what: &'static str,
+    attempts: u32,
+    /// Wait before the second try, doubled for each one after up to
+    /// `max_backoff`.
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+/// Pacing for `migrate` at startup, before giving up on the database.
+const MIGRATE_RETRY: RetryPolicy = RetryPolicy {
+    what: "migration",
+    attempts: 10,
+    initial_backoff: Duration::from_millis(500),
+    max_backoff: Duration::from_secs(10),
+};
+
+/// Runs `migrate`, retrying with exponential backoff while the database is
+/// unreachable or still starting, as it may be right after a cold start.
+/// Any other error, or the last failed attempt, is returned.
+pub async fn migrate_with_retry(pool: &PgPool) -> Result<(), MigrateError> {
+    retry(MIGRATE_RETRY, || migrate(pool), is_transient).await
+}
+
+/// Calls `op` until it succeeds, fails with an error `is_transient` rejects,
+/// or `policy` runs out of attempts.
+async fn retry<T, E, F, Fut>(
+    policy: RetryPolicy,
+    mut op: F,
+    is_transient: impl Fn(&E) -> bool,
+) -> Result<T, E>
+where
+    E: std::fmt::Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut backoff = policy.initial_backoff;
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt < policy.attempts && is_transient(&err) => {
+                warn!(
+                    "{} attempt {attempt}/{} failed, retrying in {backoff:?}: {err}",
+                    policy.what, policy.attempts
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(policy.max_backoff);
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Whether `err` means the database couldn't be reached yet, rather than a
+/// migration that is broken and would fail again.
+fn is_transient(err: &MigrateError) -> bool {
+    let MigrateError::Execute(err) = err else {
+        return false;
+    };
+    match err {
+        sqlx::Error::Io(_) | sqlx::Error::Tls(_) | sqlx::Error::PoolTimedOut => true,
+        // 57P03: the database system is starting up
+        sqlx::Error::Database(err) => err.code().as_deref() == Some("57P03"),
+        _ => false,
+    }
+}
+
+/// Applies any pending migrations, logging each one.
+async fn migrate(pool: &PgPool) -> Result<(), MigrateError> {
+    let applied = {
+        let mut conn = pool.acquire().await?;
+        conn.ensure_migrations_table().await?;
+        conn.list_applied_migrations().await?
+    };
+    let pending: Vec<_> = MIGRATOR
+        .iter()
+        .filter(|migration| {
+            !applied
+                .iter()
+                .any(|applied| applied.version == migration.version)
+        })
+        .collect();
+    for migration in &pending {
+        info!(
+            "applying migration {} {}",
+            migration.version, migration.description
+        );
+    }
+    MIGRATOR.run(pool).await?;
+    info!(
+        "database schema up to date, {} migrations applied now",
+        pending.len()
+    );
+    Ok(())
+}
+
+/// Rebuilds the pool Shuttle hands us with our own limits and timeouts.
+/// Shuttle's pool is closed; the new one connects lazily with the same options.
+pub async fn tune_pool(pool: PgPool) -> PgPool {
+    let max_connections = db_env_or("DB_MAX_CONNECTIONS", DEFAULT_MAX_CONNECTIONS as u64) as u32;
+    let acquire_timeout = Duration::from_millis(db_env_or(
+        "DB_ACQUIRE_TIMEOUT_MS",
+        DEFAULT_ACQUIRE_TIMEOUT.as_millis() as u64,
+    ));
+    let statement_timeout_ms = db_env_or(
+        "DB_STATEMENT_TIMEOUT_MS",
+        DEFAULT_STATEMENT_TIMEOUT.as_millis() as u64,
+    );
+
+    let connect_options = pool.connect_options();
+    pool.close().await;
+    PgPoolOptions::new()
+        .max_connections(max_connections)
+        .acquire_timeout(acquire_timeout)
+        .after_connect(move |conn, _| {
+            Box::pin(async move {
+                conn.execute(format!("SET statement_timeout = {statement_timeout_ms}").as_str())
+                    .await?;
+                Ok(())
+            })
+        })
+        .connect_lazy_with((*connect_options).clone())
+}
+
+/// Reads a positive integer from the environment, warning about bad values.
+fn db_env_or(name: &str, default: u64) -> u64 {
+    match env::var(name) {
+        Ok(value) => match value.parse() {
+            Ok(0) | Err(_) => {
+                warn!("invalid value {value:?} for {name}, using default");
+                default
+            }
+            Ok(value) => value,
+        },
+        Err(_) => default,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    const FAST: RetryPolicy = RetryPolicy {
+        what: "test",
+        attempts: 3,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(2),
+    };
+
+    fn unreachable() -> MigrateError {
+        MigrateError::Execute(sqlx::Error::PoolTimedOut)
+    }
+
+    /// Runs `retry` over `results` in order, returning its outcome and the
+    /// number of attempts made.
+    async fn run(results: Vec<Result<(), MigrateError>>) -> (Result<(), MigrateError>, usize) {
+        let calls = Cell::new(0);
+        let mut results = results.into_iter();
+        let outcome = retry(
+            FAST,
+            || {
+                calls.set(calls.get() + 1);
+                let result = results.next().expect("no more attempts expected");
+                async move { result }
+            },
+            is_transient,
+        )
+        .await;
+        (outcome, calls.get())
+    }
+
+    #[tokio::test]
+    async fn retries_until_the_database_answers() {
+        let (outcome, calls) = run(vec![Err(unreachable()), Err(unreachable()), Ok(())]).await;
+        assert!(outcome.is_ok());
+        assert_eq!(calls, 3);
+    }
+
+    #[tokio::test]
+    async fn broken_migrations_are_not_retried() {
+        let (outcome, calls) = run(vec![Err(MigrateError::VersionMissing(1))]).await;
+        assert!(matches!(outcome, Err(MigrateError::VersionMissing(1))));
+        assert_eq!(calls, 1);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_last_attempt() {
+        let (outcome, calls) = run(vec![
+            Err(unreachable()),
+            Err(unreachable()),
+            Err(unreachable()),
+        ])
+        .await;
+        assert!(matches!(
+            outcome,
+            Err(MigrateError::Execute(sqlx::Error::PoolTimedOut))
+        ));
+        assert_eq!(calls, 3);
+    }
+}
@@ -0,0 +1,72 @@
+use std::string::FromUtf8Error;
+
+use axum::{
+    body::Body,
+    extract::multipart::MultipartError,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use tracing::*;
+
+/// Errors a handler can bail out with instead of panicking.
+///
+/// `BadRequest` covers anything caused by the client's input, `Conflict` a
+/// request that contradicts itself or the current state, `Internal`
+/// covers failures on our side (database, serialization, signing). The
+/// message is only logged; the response body stays empty like the rest of
+/// the handlers.
+#[derive(Debug)]
+pub enum AppError {
+    BadRequest(String),
+    Conflict(String),
+    Internal(String),
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        match self {
+            AppError::BadRequest(msg) => {
+                warn!("bad request: {msg}");
+                (StatusCode::BAD_REQUEST, Body::empty()).into_response()
+            }
+            AppError::Conflict(msg) => {
+                warn!("conflict: {msg}");
+                (StatusCode::CONFLICT, Body::empty()).into_response()
+            }
+            AppError::Internal(msg) => {
+                error!("internal error: {msg}");
+                (StatusCode::INTERNAL_SERVER_ERROR, Body::empty()).into_response()
+            }
+        }
+    }
+}
+
+impl From<FromUtf8Error> for AppError {
+    fn from(err: FromUtf8Error) -> Self {
+        AppError::BadRequest(format!("invalid utf-8: {err}"))
+    }
+}
+
+impl From<MultipartError> for AppError {
+    fn from(err: MultipartError) -> Self {
+        AppError::BadRequest(format!("invalid multipart: {err}"))
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::Internal(format!("json serialization: {err}"))
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for AppError {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        AppError::Internal(format!("jwt: {err}"))
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(err: sqlx::Error) -> Self {
+        AppError::Internal(format!("database: {err}"))
+    }
+}
@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+use axum::{Json, Router, extract::State, http::StatusCode, routing::get};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use tokio::time::timeout;
+use tracing::*;
+
+pub fn router(pool: PgPool) -> Router {
+    Router::new().route("/health", get(health)).with_state(pool)
+}
+
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(2);
+
+async fn health(State(pool): State<PgPool>) -> (StatusCode, Json<Value>) {
+    match timeout(HEALTH_TIMEOUT, sqlx::query("SELECT 1").execute(&pool)).await {
+        Ok(Ok(_)) => (StatusCode::OK, Json(json!({ "status": "ok" }))),
+        Ok(Err(err)) => {
+            warn!("health check query failed: {:?}", err);
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({ "status": "degraded" })),
+            )
+        }
+        Err(_) => {
+            warn!("health check timed out after {:?}", HEALTH_TIMEOUT);
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({ "status": "degraded" })),
+            )
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        LazyLock, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::Instant,
+};
+
+use axum::{
+    Router,
+    extract::{MatchedPath, Request, State},
+    http::header::CONTENT_TYPE,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use sqlx::PgPool;
+use tracing::*;
+
+/// Milk tokens left in the day 9 bucket as of the last bucket operation.
+pub static MILK_AVAILABLE: AtomicUsize = AtomicUsize::new(0);
+/// Whether the day 12 game has a winner or a full board.
+pub static GAME_FINISHED: AtomicBool = AtomicBool::new(false);
+
+/// Upper bounds of the latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Default)]
+struct Histogram {
+    /// Non-cumulative counts per bucket, plus one for `+Inf`.
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[bucket] += 1;
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    /// Keyed by route and status class, e.g. `("/19/cite/{id}", "2xx")`.
+    requests: BTreeMap<(String, String), u64>,
+    latency: BTreeMap<String, Histogram>,
+}
+
+static REGISTRY: LazyLock<Mutex<Registry>> = LazyLock::new(Default::default);
+
+pub fn router(pool: PgPool) -> Router {
+    Router::new()
+        .route("/metrics", get(metrics))
+        .with_state(pool)
+}
+
+/// Middleware recording the request count and latency of every matched route.
+pub async fn track(request: Request, next: Next) -> Response {
+    // label by route template rather than raw path to keep cardinality bounded
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", |path| path.as_str())
+        .to_string();
+    let started = Instant::now();
+    let response = next.run(request).await;
+    let elapsed = started.elapsed().as_secs_f64();
+    let status = format!("{}xx", response.status().as_u16() / 100);
+
+    let mut registry = REGISTRY.lock().unwrap();
+    *registry
+        .requests
+        .entry((route.clone(), status))
+        .or_default() += 1;
+    registry.latency.entry(route).or_default().observe(elapsed);
+    response
+}
+
+async fn metrics(State(pool): State<PgPool>) -> impl IntoResponse {
+    // fetched before locking the registry, which must not be held across awaits
+    let quotes =
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM quotes WHERE deleted_at IS NULL")
+            .fetch_one(&pool)
+            .await;
+
+    let mut out = String::new();
+    {
+        let registry = REGISTRY.lock().unwrap();
+        out.push_str("# HELP http_requests_total Requests handled, by route and status class.\n");
+        out.push_str("# TYPE http_requests_total counter\n");
+        for ((route, status), count) in &registry.requests {
+            let route = escape_label(route);
+            writeln!(
+                out,
+                "http_requests_total{{route=\"{route}\",status=\"{status}\"}} {count}"
+            )
+            .unwrap();
+        }
+        out.push_str("# HELP http_request_duration_seconds Request latency, by route.\n");
+        out.push_str("# TYPE http_request_duration_seconds histogram\n");
+        for (route, histogram) in &registry.latency {
+            let route = escape_label(route);
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(&histogram.buckets) {
+                cumulative += count;
+                writeln!(
+                    out,
+                    "http_request_duration_seconds_bucket{{route=\"{route}\",le=\"{bound}\"}} {cumulative}"
+                )
+                .unwrap();
+            }
+            writeln!(
+                out,
+                "http_request_duration_seconds_bucket{{route=\"{route}\",le=\"+Inf\"}} {}",
+                histogram.count
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "http_request_duration_seconds_sum{{route=\"{route}\"}} {}",
+                histogram.sum
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "http_request_duration_seconds_count{{route=\"{route}\"}} {}",
+                histogram.count
+            )
+            .unwrap();
+        }
+    }
+
+    out.push_str("# HELP milk_available Milk tokens left in the day 9 bucket.\n");
+    out.push_str("# TYPE milk_available gauge\n");
+    writeln!(
+        out,
+        "milk_available {}",
+        MILK_AVAILABLE.load(Ordering::SeqCst)
+    )
+    .unwrap();
+    match quotes {
+        Ok(quotes) => {
+            out.push_str("# HELP quotes Quotes stored for day 19.\n");
+            out.push_str("# TYPE quotes gauge\n");
+            writeln!(out, "quotes {quotes}").unwrap();
+        }
+        Err(err) => warn!("metrics: error counting quotes: {:?}", err),
+    }
+    out.push_str("# HELP game_finished Whether the day 12 game is over.\n");
+    out.push_str("# TYPE game_finished gauge\n");
+    writeln!(
+        out,
+        "game_finished {}",
+        GAME_FINISHED.load(Ordering::SeqCst) as u8
+    )
+    .unwrap();
+
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], out)
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
@@ -0,0 +1,226 @@
+//! Minimal MessagePack decoder producing `serde_json::Value`s.
+//!
+//! Binary and extension types have no JSON counterpart and are rejected, as
+//! are maps with non-string keys.
+
+use serde_json::{Map, Number, Value};
+
+/// Deepest array/map nesting accepted, so hostile input can't blow the stack.
+const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DecodeError;
+
+/// Decodes a single MessagePack value spanning all of `bytes`.
+pub fn from_slice(bytes: &[u8]) -> Result<Value, DecodeError> {
+    let mut decoder = Decoder { bytes, pos: 0 };
+    let value = decoder.value(0)?;
+    if decoder.pos != bytes.len() {
+        return Err(DecodeError);
+    }
+    Ok(value)
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Decoder<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], DecodeError> {
+        let end = self.pos.checked_add(len).ok_or(DecodeError)?;
+        let slice = self.bytes.get(self.pos..end).ok_or(DecodeError)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        Ok(self.take(N)?.try_into().expect("took exactly N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, DecodeError> {
+        Ok(u16::from_be_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(u32::from_be_bytes(self.array()?))
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, DecodeError> {
+        if depth > MAX_DEPTH {
+            return Err(DecodeError);
+        }
+        let marker = self.u8()?;
+        Ok(match marker {
+            0x00..=0x7f => Value::from(marker),
+            0x80..=0x8f => self.map((marker & 0x0f) as usize, depth)?,
+            0x90..=0x9f => self.seq((marker & 0x0f) as usize, depth)?,
+            0xa0..=0xbf => self.str((marker & 0x1f) as usize)?,
+            0xc0 => Value::Null,
+            0xc2 => Value::Bool(false),
+            0xc3 => Value::Bool(true),
+            0xca => float(f32::from_be_bytes(self.array()?) as f64)?,
+            0xcb => float(f64::from_be_bytes(self.array()?))?,
+            0xcc => Value::from(self.u8()?),
+            0xcd => Value::from(self.u16()?),
+            0xce => Value::from(self.u32()?),
+            0xcf => Value::from(u64::from_be_bytes(self.array()?)),
+            0xd0 => Value::from(i8::from_be_bytes(self.array()?)),
+            0xd1 => Value::from(i16::from_be_bytes(self.array()?)),
+            0xd2 => Value::from(i32::from_be_bytes(self.array()?)),
+            0xd3 => Value::from(i64::from_be_bytes(self.array()?)),
+            0xd9 => {
+                let len = self.u8()? as usize;
+                self.str(len)?
+            }
+            0xda => {
+                let len = self.u16()? as usize;
+                self.str(len)?
+            }
+            0xdb => {
+                let len = self.u32()? as usize;
+                self.str(len)?
+            }
+            0xdc => {
+                let len = self.u16()? as usize;
+                self.seq(len, depth)?
+            }
+            0xdd => {
+                let len = self.u32()? as usize;
+                self.seq(len, depth)?
+            }
+            0xde => {
+                let len = self.u16()? as usize;
+                self.map(len, depth)?
+            }
+            0xdf => {
+                let len = self.u32()? as usize;
+                self.map(len, depth)?
+            }
+            0xe0..=0xff => Value::from(marker as i8),
+            // 0xc1 is never used; bin and ext types have no JSON form
+            _ => return Err(DecodeError),
+        })
+    }
+
+    fn str(&mut self, len: usize) -> Result<Value, DecodeError> {
+        let bytes = self.take(len)?;
+        let s = std::str::from_utf8(bytes).map_err(|_| DecodeError)?;
+        Ok(Value::String(s.to_string()))
+    }
+
+    fn seq(&mut self, len: usize, depth: usize) -> Result<Value, DecodeError> {
+        // every element takes at least one byte, so don't trust `len` for the allocation
+        let mut items = Vec::with_capacity(len.min(self.bytes.len() - self.pos));
+        for _ in 0..len {
+            items.push(self.value(depth + 1)?);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn map(&mut self, len: usize, depth: usize) -> Result<Value, DecodeError> {
+        let mut map = Map::new();
+        for _ in 0..len {
+            let Value::String(key) = self.value(depth + 1)? else {
+                return Err(DecodeError);
+            };
+            let value = self.value(depth + 1)?;
+            map.insert(key, value);
+        }
+        Ok(Value::Object(map))
+    }
+}
+
+fn float(value: f64) -> Result<Value, DecodeError> {
+    Number::from_f64(value)
+        .map(Value::Number)
+        .ok_or(DecodeError)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn decodes_a_manifest() {
+        // {"package": {"name": "gift", "keywords": ["Christmas 2024"], "version": 1.5}}
+        let mut bytes = vec![0x81, 0xa7];
+        bytes.extend(b"package");
+        bytes.extend([0x83, 0xa4]);
+        bytes.extend(b"name");
+        bytes.push(0xa4);
+        bytes.extend(b"gift");
+        bytes.push(0xa8);
+        bytes.extend(b"keywords");
+        bytes.extend([0x91, 0xae]);
+        bytes.extend(b"Christmas 2024");
+        bytes.push(0xa7);
+        bytes.extend(b"version");
+        bytes.push(0xcb);
+        bytes.extend(1.5f64.to_be_bytes());
+        assert_eq!(
+            from_slice(&bytes),
+            Ok(
+                json!({"package": {"name": "gift", "keywords": ["Christmas 2024"], "version": 1.5}})
+            )
+        );
+    }
+
+    #[test]
+    fn decodes_scalars() {
+        assert_eq!(from_slice(&[0xc0]), Ok(Value::Null));
+        assert_eq!(from_slice(&[0xc3]), Ok(json!(true)));
+        assert_eq!(from_slice(&[0xff]), Ok(json!(-1)));
+        assert_eq!(from_slice(&[0xcd, 0x01, 0x00]), Ok(json!(256)));
+        assert_eq!(from_slice(&[0xd1, 0xff, 0x00]), Ok(json!(-256)));
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        assert_eq!(from_slice(&[]), Err(DecodeError));
+        // a 5-byte string with only 2 bytes present
+        assert_eq!(from_slice(&[0xa5, b'h', b'i']), Err(DecodeError));
+        // an array announcing 2 items with only one
+        assert_eq!(from_slice(&[0x92, 0x01]), Err(DecodeError));
+        assert_eq!(from_slice(&[0xcd, 0x01]), Err(DecodeError));
+    }
+
+    #[test]
+    fn rejects_trailing_bytes() {
+        assert_eq!(from_slice(&[0x01, 0x02]), Err(DecodeError));
+    }
+
+    #[test]
+    fn rejects_non_string_map_keys() {
+        // {1: "a"}
+        assert_eq!(from_slice(&[0x81, 0x01, 0xa1, b'a']), Err(DecodeError));
+        // {nil: "a"}
+        assert_eq!(from_slice(&[0x81, 0xc0, 0xa1, b'a']), Err(DecodeError));
+    }
+
+    #[test]
+    fn rejects_binary_and_extension_types() {
+        assert_eq!(from_slice(&[0xc4, 0x01, 0x00]), Err(DecodeError));
+        assert_eq!(from_slice(&[0xd4, 0x01, 0x00]), Err(DecodeError));
+    }
+
+    #[test]
+    fn limits_nesting_depth() {
+        // arrays nested MAX_DEPTH deep around a nil are fine, one more isn't
+        let nested = |depth: usize| {
+            let mut bytes = vec![0x91; depth];
+            bytes.push(0xc0);
+            bytes
+        };
+        assert!(from_slice(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(from_slice(&nested(MAX_DEPTH + 1)), Err(DecodeError));
+        // far deeper than the stack could take without the limit
+        assert_eq!(from_slice(&nested(1_000_000)), Err(DecodeError));
+    }
+}
@@ -0,0 +1,772 @@
+//! Hand-maintained OpenAPI description of the day 2, 9, 16 and 19 routes.
+//!
+//! Kept next to the handlers by convention only, so a route change has to be
+//! mirrored here by hand.
+
+use axum::{Json, Router, response::Html, routing::get};
+use serde_json::{Map, Value, json};
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/openapi.json", get(openapi))
+        .route("/docs", get(docs))
+}
+
+const SWAGGER_UI_PAGE: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<title>shuttlings-cch24 API</title>
+<link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+<div id="swagger-ui"></div>
+<script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+<script>SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });</script>
+</body>
+</html>
+"##;
+
+async fn docs() -> Html<&'static str> {
+    Html(SWAGGER_UI_PAGE)
+}
+
+async fn openapi() -> Json<Value> {
+    let mut paths = Map::new();
+    for (path, item) in day_2_paths()
+        .into_iter()
+        .chain(day_9_paths())
+        .chain(day_16_paths())
+        .chain(day_19_paths())
+    {
+        paths.insert(path.to_string(), item);
+    }
+    Json(json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "shuttlings-cch24",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": {
+                "Quote": {
+                    "type": "object",
+                    "required": ["id", "author", "quote", "created_at", "version"],
+                    "properties": {
+                        "id": { "type": "string", "format": "uuid" },
+                        "author": { "type": "string" },
+                        "quote": { "type": "string" },
+                        "created_at": { "type": "string", "format": "date-time" },
+                        "version": { "type": "integer" },
+                    },
+                },
+                "QuotePost": {
+                    "type": "object",
+                    "required": ["author", "quote"],
+                    "properties": {
+                        "author": { "type": "string", "maxLength": 256 },
+                        "quote": { "type": "string", "maxLength": 4096 },
+                    },
+                },
+                "ErrorBody": {
+                    "type": "object",
+                    "required": ["error", "code"],
+                    "properties": {
+                        "error": { "type": "string" },
+                        "code": { "type": "string" },
+                    },
+                },
+            },
+        },
+    }))
+}
+
+fn param(location: &str, name: &str, schema: Value, required: bool) -> Value {
+    json!({ "in": location, "name": name, "required": required, "schema": schema })
+}
+
+fn query(name: &str, schema: Value, required: bool) -> Value {
+    param("query", name, schema, required)
+}
+
+fn string() -> Value {
+    json!({ "type": "string" })
+}
+
+fn formatted(format: &str) -> Value {
+    json!({ "type": "string", "format": format })
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+/// A response with `description`, and a body of `schema` if there is one.
+fn response(description: &str, content_type: &str, schema: Option<Value>) -> Value {
+    match schema {
+        Some(schema) => json!({
+            "description": description,
+            "content": { content_type: { "schema": schema } },
+        }),
+        None => json!({ "description": description }),
+    }
+}
+
+fn text(description: &str) -> Value {
+    response(description, "text/plain", Some(string()))
+}
+
+fn error(description: &str) -> Value {
+    response(
+        description,
+        "application/json",
+        Some(schema_ref("ErrorBody")),
+    )
+}
+
+fn json_body(schema: Value) -> Value {
+    json!({ "required": true, "content": { "application/json": { "schema": schema } } })
+}
+
+fn day_2_paths() -> Vec<(&'static str, Value)> {
+    let rounds = query(
+        "rounds",
+        json!({ "type": "integer", "minimum": 1, "maximum": 1000, "default": 1 }),
+        false,
+    );
+    let address = |v6: bool| formatted(if v6 { "ipv6" } else { "ipv4" });
+    // the bare address by default, `{field: address}` for `Accept: application/json`
+    let negotiated = |description: &str, field: &str, v6: bool| {
+        json!({
+            "description": description,
+            "content": {
+                "text/plain": { "schema": string() },
+                "application/json": { "schema": {
+                    "type": "object",
+                    "properties": { field: address(v6) },
+                    "required": [field],
+                }},
+            },
+        })
+    };
+    vec![
+        (
+            "/2/dest",
+            json!({ "get": {
+                "summary": "Encrypt an IPv4 address with a key",
+                "parameters": [
+                    query("from", address(false), true),
+                    query("key", address(false), true),
+                    rounds.clone(),
+                ],
+                "responses": {
+                    "200": negotiated("Destination address", "dest", false),
+                    "400": { "description": "Bad query" },
+                },
+            }}),
+        ),
+        (
+            "/2/key",
+            json!({ "get": {
+                "summary": "Recover the IPv4 key between two addresses",
+                "parameters": [
+                    query("from", address(false), true),
+                    query("to", address(false), true),
+                    rounds,
+                ],
+                "responses": {
+                    "200": negotiated("Key", "key", false),
+                    "400": { "description": "Bad query" },
+                },
+            }}),
+        ),
+        (
+            "/2/cidr",
+            json!({ "get": {
+                "summary": "Encrypt every host address of an IPv4 network",
+                "parameters": [
+                    query("net", string(), true),
+                    query("key", address(false), true),
+                ],
+                "responses": {
+                    "200": text("Newline-separated destination addresses"),
+                    "400": { "description": "Malformed network or prefix shorter than /16" },
+                },
+            }}),
+        ),
+        (
+            "/2/v6/dest",
+            json!({ "get": {
+                "summary": "Encrypt an IPv6 address with a key",
+                "parameters": [query("from", address(true), true), query("key", address(true), true)],
+                "responses": {
+                    "200": negotiated("Destination address", "dest", true),
+                    "400": { "description": "Bad query" },
+                },
+            }}),
+        ),
+        (
+            "/2/v6/key",
+            json!({ "get": {
+                "summary": "Recover the IPv6 key between two addresses",
+                "parameters": [query("from", address(true), true), query("to", address(true), true)],
+                "responses": {
+                    "200": negotiated("Key", "key", true),
+                    "400": { "description": "Bad query" },
+                },
+            }}),
+        ),
+        (
+            "/2/roundtrip",
+            json!({ "get": {
+                "summary": "Check that a key recovered from an encryption matches",
+                "parameters": [query("from", string(), true), query("key", string(), true)],
+                "responses": {
+                    "200": response("Roundtrip result", "application/json", Some(json!({
+                        "type": "object",
+                        "properties": {
+                            "consistent": { "type": "boolean" },
+                            "dest": string(),
+                            "recovered_key": string(),
+                        },
+                    }))),
+                    "400": { "description": "Bad or mixed-family addresses" },
+                },
+            }}),
+        ),
+        (
+            "/2/v6/batch",
+            json!({ "post": {
+                "summary": "Encrypt many IPv6 addresses at once",
+                "requestBody": json_body(json!({
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["from", "key"],
+                        "properties": { "from": address(true), "key": address(true) },
+                    },
+                })),
+                "responses": {
+                    "200": response(
+                        "Destination addresses, in request order",
+                        "application/json",
+                        Some(json!({ "type": "array", "items": address(true) })),
+                    ),
+                    "400": text("First invalid entry"),
+                },
+            }}),
+        ),
+    ]
+}
+
+fn day_9_paths() -> Vec<(&'static str, Value)> {
+    let units = [
+        "liters",
+        "gallons",
+        "litres",
+        "pints",
+        "tablespoons",
+        "milliliters",
+    ];
+    let amount = json!({ "type": "number", "minimum": 0 });
+    let properties: Map<String, Value> = units
+        .iter()
+        .map(|unit| (unit.to_string(), amount.clone()))
+        .chain([("to".to_string(), json!({ "type": "string", "enum": units }))])
+        .collect();
+    let balance = json!({
+        "type": "object",
+        "properties": {
+            "withdrawn": { "type": "boolean" },
+            "remaining": { "type": "integer" },
+        },
+    });
+    vec![
+        (
+            "/9/milk",
+            json!({ "post": {
+                "summary": "Withdraw milk, converting an amount when sent as JSON",
+                "description": "With a JSON content type, exactly one unit field is converted. \
+                    Otherwise the optional body `{\"count\": n}` withdraws n tokens.",
+                "parameters": [query("label", json!({ "type": "boolean" }), false)],
+                "requestBody": {
+                    "required": false,
+                    "content": {
+                        "application/json": { "schema": { "type": "object", "properties": properties } },
+                        "text/plain": { "schema": {
+                            "type": "object",
+                            "properties": { "count": { "type": "integer", "minimum": 1 } },
+                        }},
+                    },
+                },
+                "responses": {
+                    "200": response(
+                        "Converted amount, as `{\"<unit>\": n}` or with `label` as \
+                            `{\"value\": n, \"unit\": \"<unit>\"}`, or withdrawal confirmation",
+                        "application/json",
+                        None,
+                    ),
+                    "400": { "description": "Bad body" },
+                    "429": {
+                        "description": "No milk available",
+                        "headers": { "Retry-After": { "schema": { "type": "integer" } } },
+                    },
+                },
+            }}),
+        ),
+        (
+            "/9/refill",
+            json!({ "post": {
+                "summary": "Fill the bucket to the brim, or to `tokens`",
+                "requestBody": {
+                    "required": false,
+                    "content": { "application/json": { "schema": {
+                        "type": "object",
+                        "required": ["tokens"],
+                        "properties": { "tokens": { "type": "integer", "minimum": 0 } },
+                    }}},
+                },
+                "responses": {
+                    "200": { "description": "Refilled" },
+                    "400": { "description": "Malformed body or more tokens than the bucket holds" },
+                },
+            }}),
+        ),
+        (
+            "/9/status",
+            json!({ "get": {
+                "summary": "Milk left in the bucket",
+                "responses": { "200": response("Bucket level", "application/json", Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "available": { "type": "integer" },
+                        "max": { "type": "integer" },
+                    },
+                }))) },
+            }}),
+        ),
+        (
+            "/9/withdraw",
+            json!({ "post": {
+                "summary": "Withdraw one unit of milk",
+                "responses": {
+                    "200": response("Withdrawn", "application/json", Some(balance.clone())),
+                    "429": response("No milk available", "application/json", Some(balance)),
+                },
+            }}),
+        ),
+    ]
+}
+
+fn day_16_paths() -> Vec<(&'static str, Value)> {
+    let token = json!({
+        "required": true,
+        "content": { "text/plain": { "schema": string() } },
+    });
+    vec![
+        (
+            "/16/wrap",
+            json!({ "post": {
+                "summary": "Sign a JSON payload into the gift cookie",
+                "parameters": [
+                    query("alg", json!({ "type": "string", "enum": ["HS256", "HS384", "HS512"] }), false),
+                    query("ttl", json!({ "type": "integer", "minimum": 1, "maximum": 31536000 }), false),
+                    query("kid", string(), false),
+                ],
+                "requestBody": json_body(json!({})),
+                "responses": {
+                    "200": {
+                        "description": "Signed",
+                        "headers": { "Set-Cookie": { "schema": string() } },
+                    },
+                    "400": { "description": "Unsupported algorithm, ttl or kid" },
+                    "409": { "description": "ttl given for a payload that already has exp" },
+                },
+            }}),
+        ),
+        (
+            "/16/unwrap",
+            json!({ "get": {
+                "summary": "Read back the gift cookie's payload",
+                "parameters": [param("cookie", "gift", string(), true)],
+                "responses": {
+                    "200": response("Payload", "application/json", Some(json!({}))),
+                    "400": { "description": "Missing or malformed cookie" },
+                    "401": { "description": "Bad signature, expired or unknown kid" },
+                },
+            }}),
+        ),
+        (
+            "/16/decode",
+            json!({ "post": {
+                "summary": "Decode a token signed with Santa's key",
+                "parameters": [
+                    query("check_exp", json!({ "type": "boolean" }), false),
+                    query("include_header", json!({ "type": "boolean" }), false),
+                ],
+                "requestBody": token.clone(),
+                "responses": {
+                    "200": response("Claims", "application/json", Some(json!({}))),
+                    "400": { "description": "Malformed token" },
+                    "401": { "description": "Invalid signature or expired" },
+                },
+            }}),
+        ),
+        (
+            "/16/verify",
+            json!({ "post": {
+                "summary": "Check a token without returning its claims",
+                "requestBody": token,
+                "responses": { "200": response("Verdict", "application/json", Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "valid": { "type": "boolean" },
+                        "reason": { "type": "string", "enum": ["malformed", "invalid_signature", "expired"] },
+                    },
+                }))) },
+            }}),
+        ),
+    ]
+}
+
+fn day_19_paths() -> Vec<(&'static str, Value)> {
+    let quote = || response("Quote", "application/json", Some(schema_ref("Quote")));
+    let quotes = |description: &str| {
+        response(
+            description,
+            "application/json",
+            Some(json!({ "type": "array", "items": schema_ref("Quote") })),
+        )
+    };
+    let id = param("path", "id", formatted("uuid"), true);
+    let limited = error("Too many writes from this client");
+    let unauthorized = error("Missing or wrong bearer token, when API_TOKEN is set");
+    let list_params = vec![
+        query("token", string(), false),
+        query(
+            "limit",
+            json!({ "type": "integer", "minimum": 1, "maximum": 100 }),
+            false,
+        ),
+        query(
+            "sort",
+            json!({ "type": "string", "enum": ["created_at", "author", "version"] }),
+            false,
+        ),
+        query(
+            "dir",
+            json!({ "type": "string", "enum": ["asc", "desc"] }),
+            false,
+        ),
+        query("created_after", formatted("date-time"), false),
+        query("created_before", formatted("date-time"), false),
+        query(
+            "preview",
+            json!({ "type": "integer", "minimum": 1, "maximum": 4096 }),
+            false,
+        ),
+    ];
+    let page = response(
+        "A page of quotes",
+        "application/json",
+        Some(json!({
+            "type": "object",
+            "properties": {
+                "quotes": {
+                    "type": "array",
+                    "items": {
+                        "allOf": [schema_ref("Quote")],
+                        "properties": { "truncated": { "type": "boolean" } },
+                    },
+                },
+                "page": { "type": "integer" },
+                "next_token": { "type": "string", "nullable": true },
+                "total_quotes": { "type": "integer" },
+                "total_pages": { "type": "integer" },
+            },
+        })),
+    );
+    let list_error = error("Bad limit, timestamp or token");
+    let author_list_params: Vec<_> = [param("path", "author", string(), true)]
+        .into_iter()
+        .chain(list_params.iter().cloned())
+        .collect();
+    vec![
+        (
+            "/19/reset",
+            json!({ "post": {
+                "summary": "Delete every quote",
+                "responses": {
+                    "200": { "description": "Reset" },
+                    "401": unauthorized.clone(),
+                },
+            }}),
+        ),
+        (
+            "/19/cite/{id}",
+            json!({ "get": {
+                "summary": "Fetch a quote",
+                "parameters": [id.clone(), param("header", "If-None-Match", string(), false)],
+                "responses": {
+                    "200": quote(),
+                    "304": { "description": "Not modified" },
+                    "404": error("No such quote"),
+                },
+            }}),
+        ),
+        (
+            "/19/cite/{id}/version/{version}",
+            json!({ "get": {
+                "summary": "Fetch a past or current revision of a quote",
+                "parameters": [id.clone(), param("path", "version", json!({ "type": "integer" }), true)],
+                "responses": { "200": quote(), "404": error("No such quote or version") },
+            }}),
+        ),
+        (
+            "/19/remove/{id}",
+            json!({ "delete": {
+                "summary": "Move a quote to the trash",
+                "parameters": [id.clone()],
+                "responses": {
+                    "200": quote(),
+                    "404": error("No such quote"),
+                    "401": unauthorized.clone(),
+                    "429": limited.clone(),
+                },
+            }}),
+        ),
+        (
+            "/19/restore/{id}",
+            json!({ "post": {
+                "summary": "Take a quote back out of the trash",
+                "parameters": [id.clone()],
+                "responses": {
+                    "200": quote(),
+                    "404": error("No such quote in the trash"),
+                    "401": unauthorized.clone(),
+                    "429": limited.clone(),
+                },
+            }}),
+        ),
+        (
+            "/19/purge",
+            json!({ "post": {
+                "summary": "Permanently delete every trashed quote",
+                "responses": {
+                    "200": response("Number of quotes deleted", "application/json", Some(json!({
+                        "type": "object",
+                        "properties": { "purged": { "type": "integer" } },
+                    }))),
+                    "401": unauthorized.clone(),
+                    "429": limited.clone(),
+                },
+            }}),
+        ),
+        (
+            "/19/remove-batch",
+            json!({ "post": {
+                "summary": "Move many quotes to the trash in one statement",
+                "requestBody": json_body(json!({
+                    "type": "object",
+                    "required": ["ids"],
+                    "properties": {
+                        "ids": { "type": "array", "maxItems": 1000, "items": formatted("uuid") },
+                    },
+                })),
+                "responses": {
+                    "200": response("Removal summary", "application/json", Some(json!({
+                        "type": "object",
+                        "properties": {
+                            "removed": { "type": "integer" },
+                            "not_found": { "type": "array", "items": formatted("uuid") },
+                        },
+                    }))),
+                    "400": error("Too many or malformed ids"),
+                    "401": unauthorized.clone(),
+                    "429": limited.clone(),
+                },
+            }}),
+        ),
+        (
+            "/19/undo/{id}",
+            json!({ "put": {
+                "summary": "Replace a quote, optionally only at an expected version",
+                "parameters": [
+                    id,
+                    query("version", json!({ "type": "integer" }), false),
+                    param("header", "If-Match", string(), false),
+                ],
+                "requestBody": json_body(schema_ref("QuotePost")),
+                "responses": {
+                    "200": quote(),
+                    "400": error("Malformed body, invalid quote or version"),
+                    "404": error("No such quote"),
+                    "409": response("Version mismatch; the current quote", "application/json", Some(schema_ref("Quote"))),
+                    "401": unauthorized.clone(),
+                    "429": limited.clone(),
+                },
+            }}),
+        ),
+        (
+            "/19/draft",
+            json!({ "post": {
+                "summary": "Create a quote",
+                "parameters": [param("header", "Idempotency-Key", string(), false)],
+                "requestBody": json_body(schema_ref("QuotePost")),
+                "responses": {
+                    "201": {
+                        "description": "Quote",
+                        "headers": { "Location": { "schema": string() } },
+                        "content": { "application/json": { "schema": schema_ref("Quote") } },
+                    },
+                    "400": error("Malformed body, invalid quote or idempotency key"),
+                    "409": error("Idempotency key used for a different quote"),
+                    "401": unauthorized.clone(),
+                    "429": limited.clone(),
+                },
+            }}),
+        ),
+        (
+            "/19/import",
+            json!({ "post": {
+                "summary": "Create many quotes atomically",
+                "requestBody": json_body(json!({
+                    "type": "array",
+                    "maxItems": 1000,
+                    "items": schema_ref("QuotePost"),
+                })),
+                "responses": {
+                    "201": response("Created ids", "application/json", Some(json!({
+                        "type": "object",
+                        "properties": {
+                            "count": { "type": "integer" },
+                            "ids": { "type": "array", "items": formatted("uuid") },
+                        },
+                    }))),
+                    "400": error("Too many or invalid quotes"),
+                    "401": unauthorized,
+                    "429": limited,
+                },
+            }}),
+        ),
+        (
+            "/19/list",
+            json!({ "get": {
+                "summary": "Page through quotes",
+                "parameters": list_params,
+                "responses": { "200": page.clone(), "400": list_error.clone() },
+            }}),
+        ),
+        (
+            "/19/author/{author}/list",
+            json!({ "get": {
+                "summary": "Page through one author's quotes",
+                "parameters": author_list_params,
+                "responses": { "200": page, "400": list_error },
+            }}),
+        ),
+        (
+            "/19/search",
+            json!({ "get": {
+                "summary": "Find quotes by author or text",
+                "parameters": [query("q", string(), true)],
+                "responses": { "200": quotes("Matching quotes"), "400": error("Missing search term") },
+            }}),
+        ),
+        (
+            "/19/export",
+            json!({ "get": {
+                "summary": "Dump every quote",
+                "parameters": [query("format", json!({ "type": "string", "enum": ["csv", "json"] }), false)],
+                "responses": {
+                    "200": {
+                        "description": "All quotes",
+                        "content": {
+                            "text/csv": { "schema": string() },
+                            "application/json": { "schema": { "type": "array", "items": schema_ref("Quote") } },
+                        },
+                    },
+                    "400": error("Unknown format"),
+                },
+            }}),
+        ),
+        (
+            "/19/count",
+            json!({ "get": {
+                "summary": "Number of quotes",
+                "responses": { "200": response("Count", "application/json", Some(json!({
+                    "type": "object",
+                    "properties": { "count": { "type": "integer" } },
+                }))) },
+            }}),
+        ),
+        (
+            "/19/authors",
+            json!({ "get": {
+                "summary": "Quote counts per author",
+                "responses": { "200": response("Authors, most quoted first", "application/json", Some(json!({
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "author": { "type": "string" },
+                            "count": { "type": "integer" },
+                        },
+                    },
+                }))) },
+            }}),
+        ),
+        (
+            "/19/recent",
+            json!({ "get": {
+                "summary": "The most recently edited quotes",
+                "parameters": [query("limit", json!({ "type": "integer", "minimum": 1, "maximum": 100 }), false)],
+                "responses": { "200": quotes("Edited quotes, newest edit first"), "400": error("Bad limit") },
+            }}),
+        ),
+        (
+            "/19/random",
+            json!({ "get": {
+                "summary": "A random quote",
+                "parameters": [query("author", string(), false)],
+                "responses": { "200": quote(), "404": error("No matching quotes") },
+            }}),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn day_2_addresses_document_both_representations() {
+        let Json(spec) = openapi().await;
+        for (path, field) in [
+            ("/2/dest", "dest"),
+            ("/2/key", "key"),
+            ("/2/v6/dest", "dest"),
+            ("/2/v6/key", "key"),
+        ] {
+            let content = &spec["paths"][path]["get"]["responses"]["200"]["content"];
+            assert!(content["text/plain"].is_object(), "{path}");
+            let schema = &content["application/json"]["schema"];
+            assert_eq!(schema["required"], json!([field]), "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn rounds_are_bounded_like_the_handlers() {
+        let Json(spec) = openapi().await;
+        for path in ["/2/dest", "/2/key"] {
+            let rounds = spec["paths"][path]["get"]["parameters"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|param| param["name"] == "rounds")
+                .unwrap();
+            assert_eq!(rounds["schema"]["minimum"], 1, "{path}");
+            assert_eq!(rounds["schema"]["maximum"], 1000, "{path}");
+        }
+    }
+}
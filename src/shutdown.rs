@@ -0,0 +1,105 @@
+use std::{
+    future::IntoFuture,
+    net::SocketAddr,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use axum::{Router, extract::Request, middleware::Next, response::Response};
+use shuttle_runtime::{CustomError, Error};
+use tokio::{net::TcpListener, signal, sync::watch, time::sleep};
+use tracing::*;
+
+/// How long in-flight requests get to finish once a shutdown signal arrives.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Requests currently being handled.
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+/// Serves the router like `shuttle_axum::AxumService`, but stops accepting
+/// connections on SIGTERM/SIGINT and waits for in-flight requests to finish.
+pub struct GracefulAxum(pub Router);
+
+#[shuttle_runtime::async_trait]
+impl shuttle_runtime::Service for GracefulAxum {
+    async fn bind(mut self, addr: SocketAddr) -> Result<(), Error> {
+        let listener = TcpListener::bind(addr).await.map_err(CustomError::new)?;
+        // carries the number of requests in flight when the signal arrived
+        let (shutdown_tx, mut shutdown_rx) = watch::channel(None);
+        let drained_rx = shutdown_rx.clone();
+        // connect info lets per-client middleware fall back to the peer address
+        let service = self.0.into_make_service_with_connect_info::<SocketAddr>();
+        let server = axum::serve(listener, service).with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            let draining = IN_FLIGHT.load(Ordering::SeqCst);
+            info!("shutting down, draining {draining} in-flight requests");
+            let _ = shutdown_tx.send(Some(draining));
+        });
+        let deadline = async move {
+            let _ = shutdown_rx.changed().await;
+            sleep(DRAIN_TIMEOUT).await;
+        };
+        tokio::select! {
+            result = server.into_future() => {
+                result.map_err(CustomError::new)?;
+                if let Some(draining) = *drained_rx.borrow() {
+                    info!("drained {draining} in-flight requests");
+                }
+            }
+            _ = deadline => {
+                let draining = drained_rx.borrow().unwrap_or_default();
+                let abandoned = IN_FLIGHT.load(Ordering::SeqCst);
+                warn!(
+                    "drain timed out after {:?}, drained {} of {} in-flight requests",
+                    DRAIN_TIMEOUT,
+                    draining.saturating_sub(abandoned),
+                    draining
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Middleware counting the requests in flight, for the shutdown log.
+pub async fn track_in_flight(request: Request, next: Next) -> Response {
+    struct Guard;
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+    // decrement even if the handler future is dropped mid-request
+    let _guard = Guard;
+    next.run(request).await
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = signal::ctrl_c().await {
+            warn!("error listening for SIGINT: {:?}", err);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                warn!("error listening for SIGTERM: {:?}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
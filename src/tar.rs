@@ -0,0 +1,171 @@
+//! Minimal reader for uncompressed tar archives (ustar and GNU).
+//!
+//! Only regular files are returned. GNU long names are honoured, pax extended
+//! headers are skipped, so a path only recorded in one falls back to the
+//! truncated name in the entry's own header.
+
+const BLOCK_LEN: usize = 512;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DecodeError;
+
+pub struct Entry<'a> {
+    pub path: String,
+    pub data: &'a [u8],
+}
+
+/// The regular files in `bytes`, in archive order.
+pub fn files(bytes: &[u8]) -> Result<Vec<Entry<'_>>, DecodeError> {
+    let mut entries = Vec::new();
+    let mut long_name = None;
+    let mut pos = 0;
+    // a missing end-of-archive marker is tolerated, a truncated entry is not
+    while pos < bytes.len() {
+        let header = bytes.get(pos..pos + BLOCK_LEN).ok_or(DecodeError)?;
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        if octal(&header[148..156])? != checksum(header) {
+            return Err(DecodeError);
+        }
+        let size = usize::try_from(octal(&header[124..136])?).map_err(|_| DecodeError)?;
+        let start = pos + BLOCK_LEN;
+        let end = start.checked_add(size).ok_or(DecodeError)?;
+        let data = bytes.get(start..end).ok_or(DecodeError)?;
+        pos = start + size.div_ceil(BLOCK_LEN) * BLOCK_LEN;
+        match header[156] {
+            b'L' => long_name = Some(c_string(data)),
+            b'0' | 0 => {
+                let path = long_name.take().unwrap_or_else(|| header_path(header));
+                entries.push(Entry { path, data });
+            }
+            _ => long_name = None,
+        }
+    }
+    Ok(entries)
+}
+
+fn header_path(header: &[u8]) -> String {
+    let name = c_string(&header[..100]);
+    // ustar splits long paths into a prefix and a name
+    if &header[257..262] == b"ustar" {
+        let prefix = c_string(&header[345..500]);
+        if !prefix.is_empty() {
+            return format!("{prefix}/{name}");
+        }
+    }
+    name
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..len]).into_owned()
+}
+
+/// Parses a NUL- or space-terminated octal field.
+fn octal(field: &[u8]) -> Result<u64, DecodeError> {
+    let digits = c_string(field);
+    u64::from_str_radix(digits.trim(), 8).map_err(|_| DecodeError)
+}
+
+/// Sum of the header bytes with the checksum field counted as spaces.
+fn checksum(header: &[u8]) -> u64 {
+    header
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| if (148..156).contains(&i) { b' ' } else { b } as u64)
+        .sum()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// A ustar header for `path` followed by `data`, padded to whole blocks.
+    pub fn entry(path: &str, typeflag: u8, data: &[u8]) -> Vec<u8> {
+        let mut header = [0u8; BLOCK_LEN];
+        header[..path.len()].copy_from_slice(path.as_bytes());
+        header[100..107].copy_from_slice(b"0000644");
+        header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        header[156] = typeflag;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        let checksum = checksum(&header);
+        header[148..155].copy_from_slice(format!("{checksum:06o}\0").as_bytes());
+        let mut bytes = header.to_vec();
+        bytes.extend(data);
+        bytes.resize(bytes.len().div_ceil(BLOCK_LEN) * BLOCK_LEN, 0);
+        bytes
+    }
+
+    /// `entries` followed by the two zero blocks that end an archive.
+    pub fn archive(entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = entries.concat();
+        bytes.extend([0; 2 * BLOCK_LEN]);
+        bytes
+    }
+
+    fn paths(bytes: &[u8]) -> Vec<(String, Vec<u8>)> {
+        files(bytes)
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.path, entry.data.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn reads_regular_files_in_order() {
+        let bytes = archive(&[
+            entry("gift/", b'5', b""),
+            entry("gift/Cargo.toml", b'0', b"[package]\n"),
+            entry("gift/src/lib.rs", b'0', &[b'x'; 600]),
+        ]);
+        assert_eq!(
+            paths(&bytes),
+            [
+                ("gift/Cargo.toml".to_string(), b"[package]\n".to_vec()),
+                ("gift/src/lib.rs".to_string(), vec![b'x'; 600]),
+            ]
+        );
+    }
+
+    #[test]
+    fn honours_gnu_long_names_and_ustar_prefixes() {
+        let long_path = format!("{}/Cargo.toml", "d".repeat(120));
+        let mut long_name = long_path.clone().into_bytes();
+        long_name.push(0);
+        let mut prefixed = entry("Cargo.toml", b'0', b"");
+        prefixed[345..349].copy_from_slice(b"gift");
+        let checksum = checksum(&prefixed[..BLOCK_LEN]);
+        prefixed[148..155].copy_from_slice(format!("{checksum:06o}\0").as_bytes());
+        let bytes = archive(&[
+            entry("././@LongLink", b'L', &long_name),
+            entry("truncated", b'0', b""),
+            prefixed,
+        ]);
+        let paths: Vec<_> = paths(&bytes).into_iter().map(|(path, _)| path).collect();
+        assert_eq!(paths, [long_path, "gift/Cargo.toml".to_string()]);
+    }
+
+    #[test]
+    fn tolerates_a_missing_end_marker() {
+        let bytes = entry("Cargo.toml", b'0', b"[package]\n");
+        assert_eq!(paths(&bytes).len(), 1);
+    }
+
+    #[test]
+    fn rejects_a_bad_checksum() {
+        let mut bytes = archive(&[entry("Cargo.toml", b'0', b"[package]\n")]);
+        bytes[0] = b'X';
+        assert_eq!(files(&bytes).err(), Some(DecodeError));
+    }
+
+    #[test]
+    fn rejects_a_truncated_entry() {
+        let bytes = entry("Cargo.toml", b'0', &[b'x'; 600]);
+        // the header promises 600 bytes of data
+        assert_eq!(files(&bytes[..BLOCK_LEN + 100]).err(), Some(DecodeError));
+        // a header cut short
+        assert_eq!(files(&bytes[..100]).err(), Some(DecodeError));
+    }
+}